@@ -0,0 +1,17 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_auth_webserver_code(_backend_code_and_description: &str) {
+    /// INPUT: Takes in the BACKEND_CODE of an actix-web server and its PROJECT_DESCRIPTION
+    /// FUNCTION: Adds user authentication to the BACKEND_CODE while keeping every existing feature working:
+    ///   1. POST /register takes {"username": string, "password": string} and stores the user with a salted password hash, never the plain password
+    ///   2. POST /login takes {"username": string, "password": string} and returns {"token": string} for a correct password, 401 otherwise
+    ///   3. POST /logout invalidates the token sent in the Authorization header
+    ///   4. Every other route rejects requests without a valid "Authorization: Bearer <token>" header with 401 Unauthorized, using App::wrap_fn so the check runs before any extractor. Requests under /app stay public.
+    /// IMPORTANT: Hash passwords with argon2: argon2::Argon2::default().hash_password(password.as_bytes(), &salt) where the salt is argon2::password_hash::SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes()). Verify with argon2::PasswordVerifier and argon2::PasswordHash::new.
+    /// IMPORTANT: Tokens are uuid::Uuid::new_v4() strings kept in a Mutex<HashMap<String, u64>> of token to user id inside the app state
+    /// IMPORTANT: The following libraries are installed: reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors, uuid, argon2 and any library already used by the BACKEND_CODE
+    /// IMPORTANT: Keep reading the host from the HOST environment variable with "127.0.0.1" as default and the port from the PORT environment variable with 8080 as default
+    /// OUTPUT: Print ONLY the full updated code, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
@@ -9,19 +9,39 @@ pub fn print_backend_webserver_code(_project_description_and_template: &str) {
     /// IMPORTANT: The following libraries are already installed
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors
     /// No other external libraries should be used. Write functions that fit with the description from the PROJECT_DESCRIPTION
+    /// IMPORTANT: If a DATA_MODEL and SQL_MIGRATIONS are given, rusqlite is also installed. Persist data in SQLite instead of HashMaps and database.json:
+    ///   open "database.db" with rusqlite::Connection, run include_str!("../migrations/0001_init.sql") with execute_batch at startup,
+    ///   keep the connection in a Mutex inside the app state and read and write the DATA_MODEL tables with SQL queries
+    /// IMPORTANT: The server binds (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default, as in the CODE_TEMPLATE
     /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
     println!(OUTPUT)
 }
 
 #[ai_function]
 pub fn print_improved_webserver_code(_project_description_and_template: &str) {
-    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and the BACKEND_CODE written for it, and optionally a CODE_REVIEW of that code
     /// FUNCTION: Performs the following tasks:
     ///   1. Removes any bugs in the code and adds minor additional functionality
     ///   2. Makes sure everything requested in the spec from a backend standpoint was followed. If not, add the feature. No code should be implemented later. Everything should be written now.
-    ///   3. ONLY writes the code. No commentary.
+    ///   3. Addresses every finding in the CODE_REVIEW if one is given, starting with the "high" severity ones
+    ///   4. ONLY writes the code. No commentary.
     /// IMPORTANT: The following libraries are already installed. Does not use ANY libraries other than what was provided in the template
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait
+    /// IMPORTANT: If a DATA_MODEL and SQL_MIGRATIONS are given, rusqlite is also installed and data must be stored in those SQLite tables
+    /// IMPORTANT: Keep binding (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_optimized_webserver_code(_backend_code_and_load_report: &str) {
+    /// INPUT: Takes in the BACKEND_CODE of an actix-web server and a LOAD_REPORT of its GET routes with throughput and p50/p99 latency in milliseconds
+    /// FUNCTION: Makes targeted changes so the GET routes answer faster under concurrent load, keeping every route, request and response exactly the same
+    /// LOGIC: Look for the usual bottlenecks first:
+    ///   1. A global Mutex around the whole database held for every request: use an RwLock, finer grained locks or a concurrent map
+    ///   2. Writing the database file or doing other blocking I/O inside a request: move it out of the read path or into web::block
+    ///   3. Cloning whole collections to answer a request
+    /// IMPORTANT: Only use libraries already used by the BACKEND_CODE. Keep binding (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default
+    /// OUTPUT: Print ONLY the full updated code, nothing else. This function ONLY prints code.
     println!(OUTPUT)
 }
 
@@ -29,10 +49,42 @@ pub fn print_improved_webserver_code(_project_description_and_template: &str) {
 pub fn print_fixed_code(_broken_code_with_bugs: &str) {
     /// INPUT: Takes in Rust BROKEN_CODE and the ERROR_BUGS found
     /// FUNCTION: Removes bugs from code
+    /// IMPORTANT: Keep binding (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default
     /// IMPORTANT: Only prints out the new and improved code. No commentary or anything else
     println!(OUTPUT)
 }
 
+#[ai_function]
+pub fn print_fixed_code_edits(_broken_code_with_bugs: &str) {
+    /// INPUT: Takes in Rust BROKEN_CODE and the ERROR_BUGS found
+    /// FUNCTION: Removes the bugs with the smallest changes to the code, printed as SEARCH/REPLACE edits
+    /// OUTPUT: One or more blocks in exactly this format, nothing else:
+    /// <<<<<<< SEARCH
+    /// lines copied exactly from BROKEN_CODE, enough of them to match only one place
+    /// =======
+    /// the lines that replace them
+    /// >>>>>>> REPLACE
+    /// IMPORTANT: Never reprint the whole code. Code outside the edits stays as it is
+    /// IMPORTANT: Keep binding (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_improved_webserver_edits(_project_description_and_template: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and the BACKEND_CODE written for it, and optionally a CODE_REVIEW of that code
+    /// FUNCTION: Removes bugs, adds anything the PROJECT_DESCRIPTION asks for that is missing and addresses every finding in the CODE_REVIEW, "high" severity first
+    /// OUTPUT: One or more blocks in exactly this format, nothing else:
+    /// <<<<<<< SEARCH
+    /// lines copied exactly from BACKEND_CODE, enough of them to match only one place
+    /// =======
+    /// the lines that replace them
+    /// >>>>>>> REPLACE
+    /// IMPORTANT: Never reprint the whole code. Only use libraries already used by the BACKEND_CODE
+    /// IMPORTANT: Keep binding (host.as_str(), port) where host is read from the HOST environment variable with "127.0.0.1" as default and port from the PORT environment variable with 8080 as default
+    /// IMPORTANT: If a DATA_MODEL and SQL_MIGRATIONS are given, data must be stored in those SQLite tables
+    println!(OUTPUT)
+}
+
 #[ai_function]
 pub fn print_rest_api_endpoints(_code_input: &str) {
     /// INPUT: Takes in Rust webserver CODE_INPUT based on actix-web
@@ -0,0 +1,22 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_clarifying_questions(_user_request_and_goal: &str) {
+    /// INPUT: Takes in the USER_REQUEST as typed, the PROJECT_DESCRIPTION summarized from it and MAX_QUESTIONS
+    /// FUNCTION: Finds what is ambiguous or missing before a website backend can be designed and asks targeted questions about it
+    /// LOGIC: Only ask about the following topics, and only when the USER_REQUEST does not already answer them:
+    ///   "entities": which things the site stores and which fields they have
+    ///   "auth": whether users have to register and log in, and what they may do without logging in
+    ///   "persistence": whether data has to survive a restart, how long it is kept
+    ///   "data_sources": which third party APIs or feeds the data comes from
+    /// IMPORTANT: Ask at most MAX_QUESTIONS questions, most important first. Each question is short and answerable in one sentence.
+    /// IMPORTANT: Print an empty list when the USER_REQUEST is already clear
+    /// OUTPUT: Prints ONLY a JSON object in the following format, nothing else:
+    /// {
+    ///   "questions": [
+    ///     { "topic": "entities", "question": "Besides the title, what should be stored for each song?" },
+    ///     { "topic": "auth", "question": "Do users need an account to add songs?" }
+    ///   ]
+    /// }
+    println!(OUTPUT)
+}
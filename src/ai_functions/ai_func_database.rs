@@ -0,0 +1,25 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_data_model(_project_description: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION of a website backend that needs CRUD functionality, and optionally the ERRORS of a previous attempt
+    /// FUNCTION: Derives the entities the backend has to persist in a SQLite database and their fields
+    /// IMPORTANT: Entity and field names are snake_case. Entity names are plural table names such as "songs".
+    /// IMPORTANT: Every entity has exactly one primary key field called "id" of type "integer"
+    /// IMPORTANT: "field_type" is one of "integer", "real", "text", "boolean"
+    /// IMPORTANT: "references" names another entity when the field is a foreign key to its id, otherwise null
+    /// OUTPUT: Prints ONLY a JSON object in the following format, nothing else:
+    /// {
+    ///   "entities": [
+    ///     {
+    ///       "name": "songs",
+    ///       "fields": [
+    ///         { "name": "id", "field_type": "integer", "primary_key": true, "nullable": false, "references": null },
+    ///         { "name": "title", "field_type": "text", "primary_key": false, "nullable": false, "references": null },
+    ///         { "name": "artist_id", "field_type": "integer", "primary_key": false, "nullable": true, "references": "artists" }
+    ///       ]
+    ///     }
+    ///   ]
+    /// }
+    println!(OUTPUT)
+}
@@ -0,0 +1,21 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_frontend_client_code(_project_description_and_api_schema: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and the API_ENDPOINT_SCHEMA of an actix-web backend
+    /// FUNCTION: Writes a single static index.html page with inline CSS and vanilla JavaScript that lets a user call every route in the API_ENDPOINT_SCHEMA
+    /// IMPORTANT: The page is served by the backend itself, so every fetch uses a relative path such as fetch("/item") and never a full url
+    /// IMPORTANT: The first argument of every fetch call is a string literal or a template literal such as fetch(`/item/${id}`). Never pass a variable as the url.
+    /// IMPORTANT: Only call routes listed in the API_ENDPOINT_SCHEMA, with the method listed for them. Build forms from the "request_body" fields and render the "response" fields.
+    /// IMPORTANT: No frameworks, no build step and no external scripts or stylesheets
+    /// OUTPUT: Print ONLY the html code, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_frontend_code(_broken_client_with_errors: &str) {
+    /// INPUT: Takes in an HTML_CLIENT, the API_ENDPOINT_SCHEMA it should call and the ERRORS found in it
+    /// FUNCTION: Fixes the client so every fetch call targets a route from the API_ENDPOINT_SCHEMA using a relative string or template literal url
+    /// IMPORTANT: Only prints out the new and improved html code. No commentary or anything else
+    println!(OUTPUT)
+}
@@ -0,0 +1,24 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_code_review(_backend_code_and_description: &str) {
+    /// INPUT: Takes in the BACKEND_CODE of an actix-web server and the PROJECT_DESCRIPTION it was written for
+    /// FUNCTION: Reviews the BACKEND_CODE against the PROJECT_DESCRIPTION like a senior engineer and lists every finding
+    /// LOGIC: Each finding has one of the following categories:
+    ///   "missing_feature": something the PROJECT_DESCRIPTION asks for that the code does not do
+    ///   "spec_mismatch": something the code does differently from the PROJECT_DESCRIPTION
+    ///   "risky_pattern": code that can panic, lose data, leak secrets or misbehave under concurrent requests
+    /// LOGIC: Each finding has a severity:
+    ///   "high": the backend does not fulfil the PROJECT_DESCRIPTION or breaks at runtime
+    ///   "medium": works but is fragile or incomplete
+    ///   "low": style or minor improvements
+    /// IMPORTANT: Only report real findings. An empty list is a valid review.
+    /// OUTPUT: Prints ONLY a JSON object in the following format, nothing else:
+    /// {
+    ///   "findings": [
+    ///     { "severity": "high", "category": "missing_feature", "description": "No route deletes a song although the description asks for it" },
+    ///     { "severity": "medium", "category": "risky_pattern", "description": "get_song unwraps the mutex lock and panics if it is poisoned" }
+    ///   ]
+    /// }
+    println!(OUTPUT)
+}
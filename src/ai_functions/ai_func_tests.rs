@@ -0,0 +1,22 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_integration_tests(_backend_code_and_api_schema: &str) {
+    /// INPUT: Takes in the BACKEND_CODE of an actix-web server and its API_ENDPOINT_SCHEMA
+    /// FUNCTION: Writes a cargo integration test file tests/api_routes.rs that tests the app in process with actix_web::test
+    /// IMPORTANT: The file starts with include!("../src/main.rs"); so every handler, struct and the app state of the BACKEND_CODE can be used directly
+    /// IMPORTANT: Each test is an #[actix_web::test] async fn that builds the App the same way main does with actix_web::test::init_service, then calls it with actix_web::test::call_service
+    /// IMPORTANT: Write at least one test for every route and method in the API_ENDPOINT_SCHEMA. Build requests as actix_web::test::TestRequest::<method>().uri("<path>") where <path> is a string literal such as "/item/1", never a variable or format!
+    /// IMPORTANT: Send the "request_body" fields as json with .set_json and assert on the status code and on the "response" fields
+    /// IMPORTANT: Only use the libraries the BACKEND_CODE already uses. No other dev-dependencies are installed.
+    /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_integration_tests(_broken_tests_with_errors: &str) {
+    /// INPUT: Takes in the BACKEND_CODE, the API_ENDPOINT_SCHEMA, the INTEGRATION_TESTS in tests/api_routes.rs and the compile ERRORS in those tests
+    /// FUNCTION: Fixes the INTEGRATION_TESTS so they compile against the BACKEND_CODE, keeping include!("../src/main.rs"); and a test for every route
+    /// IMPORTANT: Only prints out the new and improved test code. No commentary or anything else
+    println!(OUTPUT)
+}
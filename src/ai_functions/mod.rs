@@ -1,3 +1,9 @@
 pub(crate) mod ai_func_architect;
+pub mod ai_func_auth;
 pub mod ai_func_backend;
+pub mod ai_func_clarifier;
+pub mod ai_func_database;
+pub mod ai_func_frontend;
 pub(crate) mod ai_func_managing;
+pub mod ai_func_review;
+pub mod ai_func_tests;
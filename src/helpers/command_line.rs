@@ -1,3 +1,5 @@
+use crate::helpers::config::run_config;
+use crate::helpers::policy::PolicyFinding;
 use crossterm::{
     ExecutableCommand,
     style::{Color, ResetColor, SetForegroundColor},
@@ -40,8 +42,29 @@ impl PrintCommand {
         // user_response.trim().to_string()
     }
 }
-pub fn confirm_safe_code() -> bool {
+// Shows the policy lint report, then asks a human to approve the code unless --yes or --strict-policy decide
+pub fn confirm_safe_code(findings: &[PolicyFinding]) -> bool {
     let mut stdout = stdout();
+    if !findings.is_empty() {
+        stdout.execute(SetForegroundColor(Color::Red)).unwrap();
+        println!();
+        println!("Security policy lint found {} issue(s):", findings.len());
+        for finding in findings {
+            println!("  {}", finding);
+        }
+        stdout.execute(ResetColor).unwrap();
+        if run_config().strict_policy {
+            println!("Blocked by --strict-policy");
+            return false;
+        }
+    }
+    if run_config().auto_approve {
+        stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
+        println!();
+        println!("WARNING: Running code written entirely by AI without review (--yes)");
+        stdout.execute(ResetColor).unwrap();
+        return true;
+    }
     loop {
         //Print the questin in specified color
         stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
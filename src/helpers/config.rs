@@ -0,0 +1,234 @@
+use crate::helpers::gates::Gate;
+use crate::models::agents_manager::agent_registry::DEFAULT_PIPELINE;
+use clap::Parser;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+pub const DEFAULT_OUTPUT_DIR: &str = "generated";
+pub const DEFAULT_LLM_TIMEOUT_SECS: u64 = 120;
+pub const DEFAULT_BUILD_TIMEOUT_SECS: u64 = 600;
+pub const DEFAULT_MAX_QUESTIONS: usize = 3;
+pub const DEFAULT_P99_THRESHOLD_MS: u64 = 100;
+pub const DEFAULT_GATES: [Gate; 1] = [Gate::Check];
+pub const DEFAULT_CLIPPY_MAX_WARNINGS: usize = 10;
+pub const DEFAULT_BUG_BUDGET: u8 = 3;
+
+// Command line arguments for scripted and interactive runs
+#[derive(Parser, Debug)]
+#[command(
+    name = "backendbro",
+    about = "An AI Agent that writes blazingly fast APIs in Rust"
+)]
+pub struct Cli {
+    /// What WebServer are we building? Prompts interactively when omitted
+    #[arg(conflicts_with_all = ["request_file", "resume"])]
+    pub request: Option<String>,
+
+    /// Read the request from a file instead
+    #[arg(short = 'f', long, conflicts_with = "resume")]
+    pub request_file: Option<PathBuf>,
+
+    /// Directory where each run creates its project directory
+    #[arg(short, long, default_value = DEFAULT_OUTPUT_DIR)]
+    pub output: PathBuf,
+
+    /// Approve AI written code without asking
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Review and edit the FactSheet between agents
+    #[arg(long)]
+    pub review: bool,
+
+    /// Stop instead of asking when the security policy lint finds anything
+    #[arg(long)]
+    pub strict_policy: bool,
+
+    /// Run the generated server without network access except loopback (needs root)
+    #[arg(long)]
+    pub isolate_network: bool,
+
+    /// Gemini model used for every AI call
+    #[arg(short, long, default_value = DEFAULT_MODEL)]
+    pub model: String,
+
+    /// Seconds to wait for a single AI call
+    #[arg(long, default_value_t = DEFAULT_LLM_TIMEOUT_SECS)]
+    pub llm_timeout: u64,
+
+    /// Seconds to wait for cargo to build the generated server
+    #[arg(long, default_value_t = DEFAULT_BUILD_TIMEOUT_SECS)]
+    pub build_timeout: u64,
+
+    /// Comma separated checks the backend has to pass once it builds, in order
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = DEFAULT_GATES)]
+    pub gates: Vec<Gate>,
+
+    /// Clippy warnings the clippy gate lets through
+    #[arg(long, default_value_t = DEFAULT_CLIPPY_MAX_WARNINGS)]
+    pub clippy_max_warnings: usize,
+
+    /// Fix attempts an agent gets for failing builds, gates or tests before it gives up
+    #[arg(long, default_value_t = DEFAULT_BUG_BUDGET)]
+    pub bug_budget: u8,
+
+    /// Load test the GET routes once the endpoint checks pass
+    #[arg(long)]
+    pub load_test: bool,
+
+    /// p99 latency in milliseconds above which the backend gets an optimization pass
+    #[arg(long, default_value_t = DEFAULT_P99_THRESHOLD_MS)]
+    pub p99_threshold_ms: u64,
+
+    /// Most clarifying questions asked about the request, 0 skips them
+    #[arg(long, default_value_t = DEFAULT_MAX_QUESTIONS)]
+    pub max_questions: usize,
+
+    /// Comma separated agents to run, in order
+    #[arg(
+        short,
+        long,
+        env = "BACKENDBRO_PIPELINE",
+        value_delimiter = ',',
+        default_values_t = DEFAULT_PIPELINE.map(String::from)
+    )]
+    pub pipeline: Vec<String>,
+
+    /// Resume a previous run from its project directory or checkpoint file
+    #[arg(short, long)]
+    pub resume: Option<PathBuf>,
+}
+
+impl Cli {
+    // Request given inline or through --request-file, if any
+    pub fn user_request(&self) -> Result<Option<String>, std::io::Error> {
+        if let Some(request) = &self.request {
+            return Ok(Some(request.trim().to_string()));
+        }
+        match &self.request_file {
+            Some(path) => Ok(Some(fs::read_to_string(path)?.trim().to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn run_config(&self) -> RunConfig {
+        RunConfig {
+            output_dir: self.output.clone(),
+            auto_approve: self.yes,
+            review: self.review,
+            strict_policy: self.strict_policy,
+            isolate_network: self.isolate_network,
+            model: self.model.clone(),
+            gates: self.gates.clone(),
+            clippy_max_warnings: self.clippy_max_warnings,
+            bug_budget: self.bug_budget,
+            load_test: self.load_test,
+            p99_threshold: Duration::from_millis(self.p99_threshold_ms),
+            max_questions: self.max_questions,
+            pipeline: self.pipeline.clone(),
+            llm_timeout: Duration::from_secs(self.llm_timeout),
+            build_timeout: Duration::from_secs(self.build_timeout),
+        }
+    }
+}
+
+// Settings shared by every agent for the duration of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunConfig {
+    pub output_dir: PathBuf,
+    pub auto_approve: bool,
+    pub review: bool,
+    pub strict_policy: bool,
+    pub isolate_network: bool,
+    pub model: String,
+    pub gates: Vec<Gate>,
+    pub clippy_max_warnings: usize,
+    pub bug_budget: u8,
+    pub load_test: bool,
+    pub p99_threshold: Duration,
+    pub max_questions: usize,
+    pub pipeline: Vec<String>,
+    pub llm_timeout: Duration,
+    pub build_timeout: Duration,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+            auto_approve: false,
+            review: false,
+            strict_policy: false,
+            isolate_network: false,
+            model: DEFAULT_MODEL.to_string(),
+            gates: DEFAULT_GATES.to_vec(),
+            clippy_max_warnings: DEFAULT_CLIPPY_MAX_WARNINGS,
+            bug_budget: DEFAULT_BUG_BUDGET,
+            load_test: false,
+            p99_threshold: Duration::from_millis(DEFAULT_P99_THRESHOLD_MS),
+            max_questions: DEFAULT_MAX_QUESTIONS,
+            pipeline: DEFAULT_PIPELINE.map(String::from).to_vec(),
+            llm_timeout: Duration::from_secs(DEFAULT_LLM_TIMEOUT_SECS),
+            build_timeout: Duration::from_secs(DEFAULT_BUILD_TIMEOUT_SECS),
+        }
+    }
+}
+
+static RUN_CONFIG: OnceLock<RunConfig> = OnceLock::new();
+
+// Must be called before any agent runs, later calls are ignored
+pub fn init_run_config(config: RunConfig) {
+    let _ = RUN_CONFIG.set(config);
+}
+
+pub fn run_config() -> &'static RunConfig {
+    RUN_CONFIG.get_or_init(RunConfig::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parses_scripted_run() {
+        let cli = Cli::try_parse_from([
+            "backendbro",
+            "a todo app",
+            "--output",
+            "/tmp/runs",
+            "--yes",
+            "--strict-policy",
+            "--model",
+            "gemini-1.5-pro",
+            "--pipeline",
+            "architect,compliance,backend",
+            "--gates",
+            "clippy,test,fmt",
+        ])
+        .unwrap();
+        assert_eq!(cli.user_request().unwrap().as_deref(), Some("a todo app"));
+        let config = cli.run_config();
+        assert_eq!(config.output_dir, PathBuf::from("/tmp/runs"));
+        assert!(config.auto_approve);
+        assert!(config.strict_policy);
+        assert_eq!(config.model, "gemini-1.5-pro");
+        assert_eq!(config.pipeline, ["architect", "compliance", "backend"]);
+        assert_eq!(config.gates, [Gate::Clippy, Gate::Test, Gate::Fmt]);
+    }
+
+    #[test]
+    fn tests_defaults_to_interactive_run() {
+        let cli = Cli::try_parse_from(["backendbro"]).unwrap();
+        assert_eq!(cli.user_request().unwrap(), None);
+        assert_eq!(cli.run_config(), RunConfig::default());
+    }
+
+    #[test]
+    fn tests_rejects_request_with_resume() {
+        let res = Cli::try_parse_from(["backendbro", "a todo app", "--resume", "checkpoint.json"]);
+        assert!(res.is_err());
+    }
+}
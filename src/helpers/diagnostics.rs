@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+
+// Errors listed in a fix prompt, the first ones usually cause the rest
+const MAX_PROMPT_DIAGNOSTICS: usize = 20;
+// Lines of code shown around an error
+const EXCERPT_CONTEXT: usize = 2;
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    code: Option<CompilerCode>,
+    level: String,
+    spans: Vec<CompilerSpan>,
+    children: Vec<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    byte_start: usize,
+    byte_end: usize,
+    is_primary: bool,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+}
+
+// Replacement rustc proposes for a byte range of a file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub message: String,
+    pub file: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+    pub machine_applicable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: Option<String>,
+    pub level: String,
+    pub message: String,
+    pub file: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+}
+
+fn suggestions(message: &CompilerMessage) -> Vec<Suggestion> {
+    let own = message.spans.iter().filter_map(|span| {
+        Some(Suggestion {
+            message: message.message.clone(),
+            file: span.file_name.clone(),
+            byte_start: span.byte_start,
+            byte_end: span.byte_end,
+            replacement: span.suggested_replacement.clone()?,
+            machine_applicable: span.suggestion_applicability.as_deref()
+                == Some("MachineApplicable"),
+        })
+    });
+    own.chain(message.children.iter().flat_map(suggestions))
+        .collect()
+}
+
+// Compiler messages from `cargo build --message-format=json`, deduplicated
+pub fn parse_cargo_messages(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    for line in stdout.lines() {
+        let Ok(CargoMessage {
+            reason,
+            message: Some(message),
+        }) = serde_json::from_str::<CargoMessage>(line)
+        else {
+            continue;
+        };
+        // "aborting due to 2 previous errors" and the like have no place in the code
+        if reason != "compiler-message" || message.spans.is_empty() {
+            continue;
+        }
+        let primary: &CompilerSpan = message
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .unwrap_or(&message.spans[0]);
+        let diagnostic: Diagnostic = Diagnostic {
+            code: message.code.as_ref().map(|code| code.code.clone()),
+            level: message.level.clone(),
+            message: message.message.clone(),
+            file: primary.file_name.clone(),
+            line_start: primary.line_start,
+            line_end: primary.line_end,
+            suggestions: suggestions(&message),
+        };
+        let duplicate: bool = diagnostics.iter().any(|known| {
+            known.level == diagnostic.level
+                && known.code == diagnostic.code
+                && known.message == diagnostic.message
+                && known.file == diagnostic.file
+                && known.line_start == diagnostic.line_start
+        });
+        if !duplicate {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+// (errors, warnings)
+pub fn count_diagnostics(diagnostics: &[Diagnostic]) -> (usize, usize) {
+    let errors: usize = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings: usize = diagnostics.iter().filter(|d| d.level == "warning").count();
+    (errors, warnings)
+}
+
+// Error list for the fix prompt, the raw stderr when cargo failed before compiling anything
+pub fn build_errors(diagnostics: &[Diagnostic], stderr: &[u8], project_dir: &Path) -> String {
+    let errors: String = format_diagnostics(diagnostics, project_dir);
+    if errors.is_empty() {
+        String::from_utf8_lossy(stderr).to_string()
+    } else {
+        errors
+    }
+}
+
+// Only files of the project itself are touched, never dependency sources
+fn is_project_file(file: &str) -> bool {
+    Path::new(file)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+// Writes rustc's machine-applicable replacements into the project files, overlapping ones are left
+// for the next build. Returns how many were applied
+pub fn apply_suggestions(diagnostics: &[Diagnostic], project_dir: &Path) -> io::Result<usize> {
+    let mut by_file: BTreeMap<&str, Vec<&Suggestion>> = BTreeMap::new();
+    for suggestion in diagnostics.iter().flat_map(|d| &d.suggestions) {
+        if suggestion.machine_applicable && is_project_file(&suggestion.file) {
+            by_file
+                .entry(&suggestion.file)
+                .or_default()
+                .push(suggestion);
+        }
+    }
+
+    let mut applied: usize = 0;
+    for (file, mut suggestions) in by_file {
+        suggestions.sort_by_key(|suggestion| (suggestion.byte_start, suggestion.byte_end));
+        suggestions.dedup_by(|a, b| {
+            (a.byte_start, a.byte_end, &a.replacement) == (b.byte_start, b.byte_end, &b.replacement)
+        });
+
+        let path = project_dir.join(file);
+        let mut source: Vec<u8> = fs::read(&path)?;
+        // From the end of the file so earlier byte offsets stay valid
+        let mut next_start: usize = source.len();
+        let mut changed: bool = false;
+        for suggestion in suggestions.iter().rev() {
+            if suggestion.byte_start > suggestion.byte_end || suggestion.byte_end > next_start {
+                continue;
+            }
+            source.splice(
+                suggestion.byte_start..suggestion.byte_end,
+                suggestion.replacement.bytes(),
+            );
+            next_start = suggestion.byte_start;
+            changed = true;
+            applied += 1;
+        }
+        if changed {
+            fs::write(&path, source)?;
+        }
+    }
+    Ok(applied)
+}
+
+// The lines around a diagnostic, its own lines marked with >
+fn excerpt(source: &str, line_start: usize, line_end: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let first: usize = line_start.saturating_sub(EXCERPT_CONTEXT).max(1);
+    let last: usize = (line_end + EXCERPT_CONTEXT).min(lines.len());
+    (first..=last)
+        .map(|number| {
+            let marker: &str = if (line_start..=line_end).contains(&number) {
+                ">"
+            } else {
+                " "
+            };
+            format!("  {} {:>4} | {}", marker, number, lines[number - 1])
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Compact error list with code excerpts for the fix prompt, empty when nothing failed to compile
+pub fn format_diagnostics(diagnostics: &[Diagnostic], project_dir: &Path) -> String {
+    format_entries(
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error()),
+        project_dir,
+    )
+}
+
+// Same list for warnings, used when a lint gate fails on them
+pub fn format_warnings(diagnostics: &[Diagnostic], project_dir: &Path) -> String {
+    format_entries(
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "warning"),
+        project_dir,
+    )
+}
+
+fn format_entries<'a>(
+    diagnostics: impl Iterator<Item = &'a Diagnostic>,
+    project_dir: &Path,
+) -> String {
+    diagnostics
+        .take(MAX_PROMPT_DIAGNOSTICS)
+        .map(|diagnostic| {
+            let code: String = diagnostic
+                .code
+                .as_ref()
+                .map(|code| format!("[{}]", code))
+                .unwrap_or_default();
+            let file: &str = diagnostic.file.as_str();
+            let mut entry: String = format!(
+                "{}{} {}:{}: {}",
+                diagnostic.level, code, file, diagnostic.line_start, diagnostic.message
+            );
+            for suggestion in &diagnostic.suggestions {
+                entry.push_str(&format!(
+                    "\n  help: {}: `{}`",
+                    suggestion.message, suggestion.replacement
+                ));
+            }
+            if let Ok(source) = fs::read_to_string(project_dir.join(file)) {
+                entry.push('\n');
+                entry.push_str(&excerpt(
+                    &source,
+                    diagnostic.line_start,
+                    diagnostic.line_end,
+                ));
+            }
+            entry
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"serde"}
+{"reason":"compiler-message","message":{"code":{"code":"unused_imports","explanation":null},"level":"warning","message":"unused import: `async_trait::async_trait`","spans":[{"file_name":"src/main.rs","line_start":2,"line_end":2,"byte_start":13,"byte_end":37,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[{"message":"remove the whole `use` item","code":null,"level":"help","spans":[{"file_name":"src/main.rs","line_start":2,"line_end":2,"byte_start":9,"byte_end":39,"is_primary":true,"suggested_replacement":"","suggestion_applicability":"MachineApplicable"}],"children":[]}]}}
+{"reason":"compiler-message","message":{"code":{"code":"E0308","explanation":"Expected type did not match the received type."},"level":"error","message":"mismatched types","spans":[{"file_name":"src/main.rs","line_start":4,"line_end":4,"byte_start":55,"byte_end":71,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[{"message":"consider borrowing here","code":null,"level":"help","spans":[{"file_name":"src/main.rs","line_start":4,"line_end":4,"byte_start":67,"byte_end":67,"is_primary":true,"suggested_replacement":"&","suggestion_applicability":"MachineApplicable"}],"children":[]}]}}
+{"reason":"compiler-message","message":{"code":{"code":"E0308","explanation":null},"level":"error","message":"mismatched types","spans":[{"file_name":"src/main.rs","line_start":4,"line_end":4,"byte_start":55,"byte_end":71,"is_primary":true,"suggested_replacement":null,"suggestion_applicability":null}],"children":[]}}
+{"reason":"compiler-message","message":{"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[],"children":[]}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn tests_parses_cargo_json() {
+        let diagnostics: Vec<Diagnostic> = parse_cargo_messages(CARGO_OUTPUT);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(count_diagnostics(&diagnostics), (1, 1));
+
+        let error: &Diagnostic = &diagnostics[1];
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!((error.file.as_str(), error.line_start), ("src/main.rs", 4));
+        assert_eq!(error.suggestions[0].replacement, "&");
+        assert!(error.suggestions[0].machine_applicable);
+    }
+
+    const MAIN_RS: &str =
+        "// songs\nuse async_trait::async_trait;\nfn main() {\n    delete_song(id);\n}\n";
+
+    fn write_project(name: &str) -> std::path::PathBuf {
+        let project_dir = std::env::temp_dir().join(format!(
+            "backendbro-diagnostics-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::write(project_dir.join("src/main.rs"), MAIN_RS).unwrap();
+        project_dir
+    }
+
+    #[test]
+    fn tests_formats_errors_with_excerpt() {
+        let project_dir = write_project("excerpt");
+
+        let prompt: String = format_diagnostics(&parse_cargo_messages(CARGO_OUTPUT), &project_dir);
+        assert_eq!(
+            prompt,
+            "error[E0308] src/main.rs:4: mismatched types
+  help: consider borrowing here: `&`
+       2 | use async_trait::async_trait;
+       3 | fn main() {
+  >    4 |     delete_song(id);
+       5 | }"
+        );
+        let _ = fs::remove_dir_all(project_dir);
+    }
+
+    #[test]
+    fn tests_applies_machine_applicable_suggestions() {
+        let project_dir = write_project("apply");
+        let diagnostics: Vec<Diagnostic> = parse_cargo_messages(CARGO_OUTPUT);
+
+        assert_eq!(apply_suggestions(&diagnostics, &project_dir).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(project_dir.join("src/main.rs")).unwrap(),
+            "// songs\nfn main() {\n    delete_song(&id);\n}\n"
+        );
+        let _ = fs::remove_dir_all(project_dir);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::helpers::config::run_config;
+use crate::helpers::diagnostics::{
+    Diagnostic, build_errors, count_diagnostics, format_warnings, parse_cargo_messages,
+};
+use crate::helpers::process::run_with_timeout;
+use crate::helpers::sandbox::sandboxed_cargo;
+use crate::helpers::server::test_project;
+use clap::ValueEnum;
+use std::path::Path;
+use std::process::Output;
+use tokio_util::sync::CancellationToken;
+
+// Test output kept for the fix prompt, the failures are at the end
+const MAX_TEST_OUTPUT_CHARS: usize = 6000;
+
+// Checks the generated project has to pass after it builds, in the order given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Gate {
+    /// `cargo check --all-targets`, also compiles tests and examples
+    Check,
+    /// `cargo clippy --all-targets`, fails above the warning threshold
+    Clippy,
+    /// `cargo test`
+    Test,
+    /// `cargo fmt --check`, after running `cargo fmt` once
+    Fmt,
+}
+
+impl Gate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gate::Check => "check",
+            Gate::Clippy => "clippy",
+            Gate::Test => "test",
+            Gate::Fmt => "fmt",
+        }
+    }
+
+    // What the fix prompt is asked to do about a failure of this gate
+    pub fn fix_instruction(&self) -> &'static str {
+        match self {
+            Gate::Check => {
+                "The server builds but other targets such as tests do not compile. Fix the errors"
+            }
+            Gate::Clippy => {
+                "The code compiles but has too many clippy warnings. Fix the warnings without changing behaviour"
+            }
+            Gate::Test => {
+                "The code compiles but tests fail. Fix the code so the failing tests pass, do not delete tests"
+            }
+            Gate::Fmt => {
+                "The code is not formatted and cargo fmt could not fix it. Fix the syntax rustfmt reports"
+            }
+        }
+    }
+}
+
+// Result of one gate, `failure` is what the fix prompt gets
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateOutcome {
+    pub gate: Gate,
+    pub passed: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub failure: Option<String>,
+}
+
+async fn cargo(
+    args: &[&str],
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> std::io::Result<Output> {
+    let (command, _sandbox) = sandboxed_cargo(args, project_dir)?;
+    run_with_timeout(command, run_config().build_timeout, cancel).await
+}
+
+fn output_text(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+// Last `max_chars` characters of the output
+fn output_tail(output: &str, max_chars: usize) -> String {
+    let skip: usize = output.chars().count().saturating_sub(max_chars);
+    output.chars().skip(skip).collect()
+}
+
+pub async fn run_gate(
+    gate: Gate,
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> std::io::Result<GateOutcome> {
+    let outcome: GateOutcome = match gate {
+        Gate::Check | Gate::Clippy => {
+            let subcommand: &str = if gate == Gate::Check {
+                "check"
+            } else {
+                "clippy"
+            };
+            let output: Output = cargo(
+                &[subcommand, "--all-targets", "--message-format=json"],
+                project_dir,
+                cancel,
+            )
+            .await?;
+            let diagnostics: Vec<Diagnostic> =
+                parse_cargo_messages(&String::from_utf8_lossy(&output.stdout));
+            let (errors, warnings) = count_diagnostics(&diagnostics);
+            let compiled: bool = output.status.success();
+            let passed: bool =
+                compiled && (gate == Gate::Check || warnings <= run_config().clippy_max_warnings);
+            let failure: Option<String> = match (passed, compiled) {
+                (true, _) => None,
+                (false, false) => Some(build_errors(&diagnostics, &output.stderr, project_dir)),
+                (false, true) => Some(format!(
+                    "{} clippy warnings, at most {} allowed:\n{}",
+                    warnings,
+                    run_config().clippy_max_warnings,
+                    format_warnings(&diagnostics, project_dir)
+                )),
+            };
+            GateOutcome {
+                gate,
+                passed,
+                errors,
+                warnings,
+                failure,
+            }
+        }
+        Gate::Test => {
+            let output: Output = test_project(project_dir, cancel).await?;
+            let passed: bool = output.status.success();
+            GateOutcome {
+                gate,
+                passed,
+                errors: usize::from(!passed),
+                warnings: 0,
+                failure: (!passed)
+                    .then(|| output_tail(&output_text(&output), MAX_TEST_OUTPUT_CHARS)),
+            }
+        }
+        Gate::Fmt => {
+            // Formatting is mechanical, only what rustfmt can't handle goes to the model
+            cargo(&["fmt"], project_dir, cancel).await?;
+            let output: Output = cargo(&["fmt", "--check"], project_dir, cancel).await?;
+            let passed: bool = output.status.success();
+            GateOutcome {
+                gate,
+                passed,
+                errors: usize::from(!passed),
+                warnings: 0,
+                failure: (!passed).then(|| output_text(&output)),
+            }
+        }
+    };
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_names_gates_and_keeps_test_tail() {
+        assert_eq!(Gate::from_str("clippy", true), Ok(Gate::Clippy));
+        assert_eq!(Gate::Fmt.name(), "fmt");
+        assert_eq!(output_tail("test songs ... FAILED", 6), "FAILED");
+        assert_eq!(output_tail("ok", 6), "ok");
+    }
+}
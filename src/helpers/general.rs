@@ -1,4 +1,5 @@
 use super::command_line::PrintCommand;
+use crate::helpers::config::run_config;
 use crate::{
     ai_functions::{
         ai_func_architect::print_project_scope, ai_func_managing::convert_user_input_to_goal,
@@ -11,17 +12,80 @@ use crate::{
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 use std::fs;
-pub const CODE_TEMPLATE_PATH: &str = "/home/aryan/BackendBro/web_template/src/code_template.rs";
-pub const WEB_TEMPLATE_PATH: &str = "/home/aryan/BackendBro/web_template";
-pub const EXEC_MAIN_PATH: &str = "/home/aryan/BackendBro/web_template/src/main.rs";
-pub const API_SCHEMA_PATH: &str = "/home/aryan/BackendBro/schemas/api_schema.json";
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+// Paths inside a generated project directory
+pub const EXEC_MAIN_PATH: &str = "src/main.rs";
+pub const API_SCHEMA_PATH: &str = "api_schema.json";
+// Template files written into every new project, embedded so the binary runs from anywhere.
+// code_template.rs is only prompt input and would sit uncompiled in src/ of the project
+const TEMPLATE_FILES: [(&str, &str); 3] = [
+    ("Cargo.toml", include_str!("../../web_template/Cargo.toml")),
+    ("Cargo.lock", include_str!("../../web_template/Cargo.lock")),
+    (
+        EXEC_MAIN_PATH,
+        include_str!("../../web_template/src/main.rs"),
+    ),
+];
+const CODE_TEMPLATE: &str = include_str!("../../web_template/src/code_template.rs");
 
-// Get Code Template
-pub fn read_code_to_template_contents() -> String {
-    fs::read_to_string(CODE_TEMPLATE_PATH).expect("Something went wrong, failed to read template")
+pub(crate) fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
 }
-pub fn read_exec_main_contents() -> String {
-    fs::read_to_string(EXEC_MAIN_PATH).expect("Something went wrong, failed to read template")
+
+// Write the web template into a fresh directory under output_dir
+pub fn create_project_dir(output_dir: &Path) -> std::io::Result<PathBuf> {
+    let timestamp: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let mut project_dir: PathBuf = output_dir.join(format!("backend-{}", timestamp));
+    let mut suffix: u32 = 1;
+    while project_dir.exists() {
+        project_dir = output_dir.join(format!("backend-{}-{}", timestamp, suffix));
+        suffix += 1;
+    }
+    fs::create_dir_all(&project_dir)?;
+    for (entry, contents) in TEMPLATE_FILES {
+        let dst: PathBuf = project_dir.join(entry);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dst, contents)?;
+    }
+    Ok(project_dir)
+}
+
+// Adds a dependency line to a generated project's Cargo.toml unless the crate is already there
+pub fn add_project_dependency(manifest: &str, dependency: &str) -> Option<String> {
+    let crate_name: &str = dependency.split('=').next()?.trim();
+    let already_added: bool = manifest
+        .lines()
+        .any(|line| line.split('=').next().map(str::trim) == Some(crate_name));
+    if already_added {
+        return Some(manifest.to_string());
+    }
+    let deps_start: usize = manifest.find("[dependencies]\n")? + "[dependencies]\n".len();
+    Some(format!(
+        "{}{}\n{}",
+        &manifest[..deps_start],
+        dependency,
+        &manifest[deps_start..]
+    ))
+}
+
+pub fn read_exec_main_contents(project_dir: &Path) -> String {
+    fs::read_to_string(project_dir.join(EXEC_MAIN_PATH))
+        .expect("Something went wrong, failed to read template")
 }
 pub fn extract_code_block(input: String) -> Option<String> {
     // Find the first set of triple backticks (```).
@@ -37,17 +101,17 @@ pub fn extract_code_block(input: String) -> Option<String> {
     // Return None if no code block is found
     None
 }
-pub fn save_backend_code(contents: &str) {
+pub fn save_backend_code(project_dir: &Path, contents: &str) {
     let parsed_code = extract_code_block(contents.to_string());
     if let Some(code) = parsed_code {
-        fs::write(EXEC_MAIN_PATH, code).expect("Failed to write main.rs");
+        fs::write(project_dir.join(EXEC_MAIN_PATH), code).expect("Failed to write main.rs");
     } else {
         eprintln!("Cannot parse the code");
     }
 }
 
-pub fn save_api_endpoints(api_endpoint: &str) {
-    fs::write(API_SCHEMA_PATH, api_endpoint).expect("Couldn't write to file");
+pub fn save_api_endpoints(project_dir: &Path, api_endpoint: &str) {
+    fs::write(project_dir.join(API_SCHEMA_PATH), api_endpoint).expect("Couldn't write to file");
 }
 
 pub fn extend_ai_function(ai_func: fn(&str) -> &'static str, func_input: &str) -> String {
@@ -60,24 +124,34 @@ pub fn extend_ai_function(ai_func: fn(&str) -> &'static str, func_input: &str) -
     )
 }
 
+// A timed out call is not sent again, the step fails so the run can be resumed later
+fn is_timeout(err: &(dyn std::error::Error + Send + 'static)) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(reqwest::Error::is_timeout)
+}
+
 pub async fn ai_task_request(
     msg_context: String,
     agent_position: &str,
     agent_operation: &str,
     function_pass: fn(&str) -> &'static str,
-) -> String {
+) -> Result<String, Box<dyn std::error::Error>> {
     let extended_msg = extend_ai_function(function_pass, &msg_context);
     PrintCommand::AICall.print_agent_message(agent_position, agent_operation);
 
     match send_request(&extended_msg).await {
-        Ok(response) => response,
-        Err(_) => match send_request(&msg_context).await {
-            Ok(fallback_response) => fallback_response,
-            Err(e) => {
-                eprintln!("Failed to call Gemini: {}", e);
-                String::from("AI service unavailable")
-            }
-        },
+        Ok(response) => Ok(response),
+        Err(e) if is_timeout(e.as_ref()) => Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!(
+                "AI call for {} timed out after {}s",
+                agent_operation,
+                run_config().llm_timeout.as_secs()
+            ),
+        ))),
+        Err(_) => send_request(&msg_context)
+            .await
+            .map_err(|e| format!("AI call for {} failed: {}", agent_operation, e).into()),
     }
 }
 pub async fn ai_task_request_decoded<T: DeserializeOwned>(
@@ -85,11 +159,17 @@ pub async fn ai_task_request_decoded<T: DeserializeOwned>(
     agent_position: &str,
     agent_operation: &str,
     function_pass: fn(&str) -> &'static str,
-) -> T {
+) -> Result<T, Box<dyn std::error::Error>> {
     let llm_response =
-        ai_task_request(msg_context, agent_position, agent_operation, function_pass).await;
+        ai_task_request(msg_context, agent_position, agent_operation, function_pass).await?;
     println!("{}", llm_response);
-    serde_json::from_str::<T>(&llm_response).expect("Failed to decode AI response from serde_json")
+    serde_json::from_str::<T>(&llm_response).map_err(|e| {
+        format!(
+            "Failed to decode AI response for {}: {}",
+            agent_operation, e
+        )
+        .into()
+    })
 }
 
 pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwest::Error> {
@@ -97,8 +177,12 @@ pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwes
     Ok(response.status().as_u16())
 }
 
+// Get Code Template
+pub fn read_code_to_template_contents() -> String {
+    CODE_TEMPLATE.to_string()
+}
 pub fn read_code_template_contents() -> String {
-    fs::read_to_string(CODE_TEMPLATE_PATH).expect("Failed to read code template")
+    CODE_TEMPLATE.to_string()
 }
 
 #[cfg(test)]
@@ -123,17 +207,51 @@ mod tests {
             "Defining user requirements",
             convert_user_input_to_goal,
         )
-        .await;
+        .await
+        .unwrap();
         let res2: ProjectScope = ai_task_request_decoded::<ProjectScope>(
             res.clone(),
             "Solutions Architect",
             "Finding Project Scope",
             print_project_scope,
         )
-        .await;
+        .await
+        .unwrap();
         dbg!(res2);
     }
 
+    #[test]
+    fn tests_create_project_dir_keeps_template_pristine() {
+        let output_dir: PathBuf =
+            std::env::temp_dir().join(format!("backendbro-projects-{}", std::process::id()));
+        let first: PathBuf = create_project_dir(&output_dir).unwrap();
+        let second: PathBuf = create_project_dir(&output_dir).unwrap();
+        assert_ne!(first, second);
+
+        save_backend_code(&first, "```rust\nfn main() {}\n```");
+        assert_eq!(read_exec_main_contents(&first).trim(), "fn main() {}");
+        assert!(second.join("Cargo.toml").is_file());
+        assert_eq!(
+            read_exec_main_contents(&second),
+            include_str!("../../web_template/src/main.rs")
+        );
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn tests_add_project_dependency() {
+        let manifest: &str = "[dependencies]\nactix-web = \"4\"\n";
+        let added: String = add_project_dependency(manifest, "actix-files = \"0.6\"").unwrap();
+        assert_eq!(
+            added,
+            "[dependencies]\nactix-files = \"0.6\"\nactix-web = \"4\"\n"
+        );
+        assert_eq!(
+            add_project_dependency(&added, "actix-files = \"0.6\"").unwrap(),
+            added
+        );
+    }
+
     #[test]
     fn tests_convert_user_input_to_goal() {
         let user_input = "Build me a web site for making stock price API requests";
@@ -0,0 +1,245 @@
+use crate::helpers::server::{call_route, sample_body, server_url_with_id};
+use crate::helpers::shape::response_mismatches;
+use crate::models::agents::agents_traits::{LifecycleStep, RouteObject};
+use reqwest::Client;
+use serde_json::{Value, json};
+
+// Id the lifecycle record is created with when the client picks the id
+pub const LIFECYCLE_ID: u64 = 4242;
+
+// Routes that create, read, update and delete one resource
+#[derive(Debug, PartialEq)]
+pub struct Lifecycle<'a> {
+    pub resource: String,
+    pub create: &'a RouteObject,
+    pub read: Option<&'a RouteObject>,
+    pub update: Option<&'a RouteObject>,
+    pub delete: Option<&'a RouteObject>,
+}
+
+// "/song/{id}" is an item route of "/song"
+fn is_item_route(collection: &str, route: &str) -> bool {
+    route
+        .strip_prefix(collection)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .is_some_and(|segment| {
+            segment.starts_with('{') && segment.ends_with('}') && !segment.contains('/')
+        })
+}
+
+fn find_route<'a>(
+    routes: &'a [RouteObject],
+    methods: &[&str],
+    matches: impl Fn(&str) -> bool,
+) -> Option<&'a RouteObject> {
+    routes.iter().find(|route_object| {
+        methods.contains(&route_object.method.to_lowercase().as_str())
+            && matches(&route_object.route)
+    })
+}
+
+// One lifecycle per static POST route, with the item routes next to it
+pub fn plan_lifecycles(routes: &[RouteObject]) -> Vec<Lifecycle<'_>> {
+    routes
+        .iter()
+        .filter(|route_object| {
+            route_object.method.eq_ignore_ascii_case("post") && !route_object.route.contains('{')
+        })
+        .map(|create| {
+            let collection: &str = create.route.trim_end_matches('/');
+            let is_item = |route: &str| is_item_route(collection, route);
+            Lifecycle {
+                resource: collection.to_string(),
+                create,
+                read: find_route(routes, &["get"], is_item),
+                update: find_route(routes, &["put", "patch"], is_item)
+                    .or_else(|| find_route(routes, &["put", "patch"], |route| route == collection)),
+                delete: find_route(routes, &["delete"], is_item),
+            }
+        })
+        .collect()
+}
+
+// Body for the create or update call, tied to the lifecycle id and with changed text on update
+fn lifecycle_body(route_object: &RouteObject, id: &str, updated: bool) -> Option<Value> {
+    let mut body: Value = sample_body(&route_object.request_body)?;
+    if let Some(fields) = body.as_object_mut() {
+        for (name, value) in fields.iter_mut() {
+            if name == "id" {
+                *value = id.parse::<u64>().map(|id| json!(id)).unwrap_or(json!(id));
+            } else if updated && value.is_string() {
+                *value = json!("updated");
+            }
+        }
+    }
+    Some(body)
+}
+
+fn id_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(id) => Some(id.to_string()),
+        Value::String(id) if !id.trim().is_empty() => Some(id.trim().to_string()),
+        _ => None,
+    }
+}
+
+// Id of the created record, from the create response or else from the body that was sent
+pub fn created_id(response_body: &str, request_body: Option<&Value>) -> Option<String> {
+    let from_response: Option<String> = match serde_json::from_str::<Value>(response_body) {
+        Ok(Value::Object(fields)) => fields.get("id").and_then(id_value),
+        Ok(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    };
+    from_response.or_else(|| request_body?.get("id").and_then(id_value))
+}
+
+async fn run_step(
+    client: &Client,
+    resource: &str,
+    step: &str,
+    route_object: &RouteObject,
+    id: &str,
+    body: Option<&Value>,
+    expected: fn(u16) -> bool,
+) -> (LifecycleStep, String) {
+    let method: String = route_object.method.to_lowercase();
+    let url: String = server_url_with_id(&route_object.route, id);
+    let (status, error, response_body) = match call_route(client, &method, &url, body, None).await {
+        Ok((status, response_body)) => (Some(status), None, response_body),
+        Err(e) => (None, Some(e.to_string()), String::new()),
+    };
+    let step: LifecycleStep = LifecycleStep {
+        resource: resource.to_string(),
+        step: step.to_string(),
+        method,
+        route: route_object.route.clone(),
+        status,
+        passed: status.is_some_and(expected),
+        error,
+        mismatches: vec![],
+    };
+    (step, response_body)
+}
+
+fn is_success(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+fn is_gone(status: u16) -> bool {
+    status == 404 || status == 410
+}
+
+// Creates a record, reads it back by its id, updates and deletes it, then checks it is gone
+pub async fn run_lifecycle(client: &Client, lifecycle: &Lifecycle<'_>) -> Vec<LifecycleStep> {
+    let resource: &str = lifecycle.resource.as_str();
+    let create_body: Option<Value> =
+        lifecycle_body(lifecycle.create, &LIFECYCLE_ID.to_string(), false);
+    let (create_step, response_body) = run_step(
+        client,
+        resource,
+        "create",
+        lifecycle.create,
+        "",
+        create_body.as_ref(),
+        is_success,
+    )
+    .await;
+    let created: bool = create_step.passed;
+    let mut steps: Vec<LifecycleStep> = vec![create_step];
+    let Some(id) = created
+        .then(|| created_id(&response_body, create_body.as_ref()))
+        .flatten()
+    else {
+        return steps;
+    };
+
+    if let Some(read) = lifecycle.read {
+        let (mut read_step, read_body) =
+            run_step(client, resource, "read", read, &id, None, is_success).await;
+        if read_step.passed {
+            read_step.mismatches = response_mismatches(&read_body, &read.response);
+            read_step.passed = read_step.mismatches.is_empty();
+        }
+        steps.push(read_step);
+    }
+    if let Some(update) = lifecycle.update {
+        let update_body: Option<Value> = lifecycle_body(update, &id, true);
+        let (update_step, _) = run_step(
+            client,
+            resource,
+            "update",
+            update,
+            &id,
+            update_body.as_ref(),
+            is_success,
+        )
+        .await;
+        steps.push(update_step);
+    }
+    if let Some(delete) = lifecycle.delete {
+        let (delete_step, _) =
+            run_step(client, resource, "delete", delete, &id, None, is_success).await;
+        let deleted: bool = delete_step.passed;
+        steps.push(delete_step);
+        if deleted && let Some(read) = lifecycle.read {
+            let (gone_step, _) = run_step(
+                client,
+                resource,
+                "confirm deleted",
+                read,
+                &id,
+                None,
+                is_gone,
+            )
+            .await;
+            steps.push(gone_step);
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn tests_plans_song_lifecycle() {
+        let schema: String = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/schemas/api_schema.json"
+        ))
+        .unwrap();
+        let routes: Vec<RouteObject> = serde_json::from_str(&schema).unwrap();
+        let lifecycles: Vec<Lifecycle> = plan_lifecycles(&routes);
+
+        assert_eq!(lifecycles.len(), 1);
+        let song: &Lifecycle = &lifecycles[0];
+        assert_eq!(song.resource, "/song");
+        assert_eq!(song.read.unwrap().route, "/song/{id}");
+        assert_eq!(
+            (
+                song.update.unwrap().method.as_str(),
+                song.update.unwrap().route.as_str()
+            ),
+            ("put", "/song")
+        );
+        assert_eq!(song.delete.unwrap().method, "delete");
+
+        let body: Value = lifecycle_body(song.update.unwrap(), "4242", true).unwrap();
+        assert_eq!(body["id"], json!(4242));
+        assert_eq!(body["title"], json!("updated"));
+    }
+
+    #[test]
+    fn tests_reads_created_id() {
+        let sent: Value = json!({ "id": 4242, "title": "sample" });
+        assert_eq!(
+            created_id(r#"{"id": 7, "title": "sample"}"#, Some(&sent)).as_deref(),
+            Some("7")
+        );
+        assert_eq!(created_id("12", None).as_deref(), Some("12"));
+        assert_eq!(created_id("Created", Some(&sent)).as_deref(), Some("4242"));
+        assert_eq!(created_id("", None), None);
+    }
+}
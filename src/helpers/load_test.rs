@@ -0,0 +1,133 @@
+use crate::models::agents::agents_traits::LoadReport;
+use reqwest::Client;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const LOAD_TEST_REQUESTS: usize = 1000;
+pub const LOAD_TEST_CONCURRENCY: usize = 32;
+
+// Latency at the given percentile (0-100) of sorted samples, in milliseconds
+pub fn percentile_ms(sorted: &[Duration], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank: usize = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1].as_secs_f64() * 1000.0
+}
+
+pub fn summarize(
+    label: &str,
+    mut latencies: Vec<Duration>,
+    errors: usize,
+    elapsed: Duration,
+) -> LoadReport {
+    latencies.sort();
+    let requests: usize = latencies.len() + errors;
+    LoadReport {
+        label: label.to_string(),
+        requests,
+        errors,
+        throughput_rps: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        p50_ms: percentile_ms(&latencies, 50.0),
+        p99_ms: percentile_ms(&latencies, 99.0),
+    }
+}
+
+// Spreads total_requests over the urls from concurrency workers, non 2xx answers count as errors
+pub async fn run_load_test(
+    client: &Client,
+    label: &str,
+    urls: &[String],
+    total_requests: usize,
+    concurrency: usize,
+) -> LoadReport {
+    let next: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let latencies: Arc<Mutex<Vec<Duration>>> = Arc::new(Mutex::new(vec![]));
+    let errors: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let urls: Arc<Vec<String>> = Arc::new(urls.to_vec());
+    let started: Instant = Instant::now();
+
+    let mut workers = vec![];
+    for _ in 0..concurrency.max(1) {
+        let (client, next, latencies, errors, urls) = (
+            client.clone(),
+            next.clone(),
+            latencies.clone(),
+            errors.clone(),
+            urls.clone(),
+        );
+        workers.push(tokio::spawn(async move {
+            loop {
+                let index: usize = next.fetch_add(1, Ordering::Relaxed);
+                if index >= total_requests || urls.is_empty() {
+                    break;
+                }
+                let request_start: Instant = Instant::now();
+                match client.get(&urls[index % urls.len()]).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        let _ = response.bytes().await;
+                        latencies.lock().await.push(request_start.elapsed());
+                    }
+                    _ => {
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }));
+    }
+    for worker in workers {
+        let _ = worker.await;
+    }
+
+    let latencies: Vec<Duration> = std::mem::take(&mut *latencies.lock().await);
+    summarize(
+        label,
+        latencies,
+        errors.load(Ordering::Relaxed),
+        started.elapsed(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_summarizes_latencies() {
+        let latencies: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let report: LoadReport = summarize("baseline", latencies, 0, Duration::from_secs(2));
+        assert_eq!(report.requests, 100);
+        assert_eq!(report.throughput_rps, 50.0);
+        assert_eq!(report.p50_ms, 50.0);
+        assert_eq!(report.p99_ms, 99.0);
+        assert_eq!(percentile_ms(&[], 99.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn tests_load_test_counts_requests() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 1024];
+                    let _ = stream.read(&mut buffer).await;
+                    let _ = stream
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                        )
+                        .await;
+                });
+            }
+        });
+
+        let urls: Vec<String> = vec![format!("http://{}/song", address)];
+        let report: LoadReport = run_load_test(&Client::new(), "baseline", &urls, 40, 4).await;
+        assert_eq!(report.requests, 40);
+        assert_eq!(report.errors, 0);
+        assert!(report.p99_ms >= report.p50_ms);
+    }
+}
@@ -1,2 +1,16 @@
 pub mod command_line;
+pub mod config;
+pub mod diagnostics;
+pub mod gates;
 pub mod general;
+pub mod lifecycle;
+pub mod load_test;
+pub mod openapi;
+pub mod outcome;
+pub mod patch;
+pub mod policy;
+pub mod process;
+pub mod review;
+pub mod sandbox;
+pub mod server;
+pub mod shape;
@@ -0,0 +1,276 @@
+use crate::models::agents::agents_traits::RouteObject;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::Path;
+
+pub const OPENAPI_PATH: &str = "openapi.json";
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+// Markers the endpoint schema uses for "no body"
+const NO_BODY: [&str; 5] = ["none", "null", "not_provided", "empty", ""];
+
+pub fn is_no_body(body: &Value) -> bool {
+    match body {
+        Value::Null => true,
+        Value::String(marker) => NO_BODY.contains(&marker.trim().to_lowercase().as_str()),
+        _ => false,
+    }
+}
+
+// JSON schema for a type name as written in the endpoint schema, such as "number" or "Vec<u64>"
+fn type_schema(type_name: &str) -> Value {
+    let type_name: &str = type_name.trim();
+    if let Some(inner) = type_name
+        .strip_prefix("Option<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        let mut schema: Value = type_schema(inner);
+        if let Some(inner_type) = schema.get("type").cloned() {
+            schema["type"] = json!([inner_type, "null"]);
+        }
+        return schema;
+    }
+    if let Some(inner) = type_name
+        .strip_prefix("Vec<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        return json!({ "type": "array", "items": type_schema(inner) });
+    }
+    match type_name.to_lowercase().as_str() {
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" | "int"
+        | "integer" => json!({ "type": "integer" }),
+        "number" | "f32" | "f64" | "float" | "double" => json!({ "type": "number" }),
+        "bool" | "boolean" => json!({ "type": "boolean" }),
+        "array" | "list" => json!({ "type": "array" }),
+        "object" | "map" => json!({ "type": "object" }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+// JSON schema inferred from a request or response body of the endpoint schema
+pub fn infer_schema(body: &Value) -> Value {
+    match body {
+        Value::String(type_name) => type_schema(type_name),
+        Value::Object(fields) => {
+            let mut properties: Map<String, Value> = Map::new();
+            let mut required: Vec<Value> = vec![];
+            for (name, field) in fields {
+                let optional: bool = field
+                    .as_str()
+                    .is_some_and(|type_name| type_name.trim().starts_with("Option<"));
+                if !optional {
+                    required.push(Value::String(name.clone()));
+                }
+                properties.insert(name.clone(), infer_schema(field));
+            }
+            json!({ "type": "object", "properties": properties, "required": required })
+        }
+        Value::Array(items) => match items.first() {
+            Some(item) => json!({ "type": "array", "items": infer_schema(item) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::Number(number) if number.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Null => json!({ "type": "null" }),
+    }
+}
+
+// "Song" for /song/{id}, the first static segment of the route
+fn resource_name(route: &str) -> String {
+    let segment: &str = route
+        .split('/')
+        .find(|segment| !segment.is_empty() && !segment.starts_with('{'))
+        .unwrap_or("root");
+    segment
+        .split(['-', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// Object schemas become components, equal shapes share one component
+fn component_ref(components: &mut Map<String, Value>, name: &str, schema: Value) -> Value {
+    if schema.get("type") == Some(&json!("array"))
+        && let Some(items) = schema.get("items").cloned()
+    {
+        return json!({ "type": "array", "items": component_ref(components, name, items) });
+    }
+    if schema.get("type") != Some(&json!("object")) {
+        return schema;
+    }
+    let existing: Option<&String> = components
+        .iter()
+        .find(|(_, known)| **known == schema)
+        .map(|(known_name, _)| known_name);
+    let component_name: String = match existing {
+        Some(known_name) => known_name.clone(),
+        None => {
+            let mut component_name: String = name.to_string();
+            let mut suffix: usize = 2;
+            while components.contains_key(&component_name) {
+                component_name = format!("{}{}", name, suffix);
+                suffix += 1;
+            }
+            components.insert(component_name.clone(), schema);
+            component_name
+        }
+    };
+    json!({ "$ref": format!("#/components/schemas/{}", component_name) })
+}
+
+fn path_parameters(route: &str) -> Vec<Value> {
+    route
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let schema: Value = if name == "id" || name.ends_with("_id") {
+                json!({ "type": "integer" })
+            } else {
+                json!({ "type": "string" })
+            };
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect()
+}
+
+fn operation_id(method: &str, route: &str) -> String {
+    let path: Vec<&str> = route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.trim_matches(['{', '}']))
+        .collect();
+    format!("{}_{}", method, path.join("_"))
+}
+
+// OpenAPI 3.1 document for the routes of the generated backend
+pub fn openapi_document(description: &str, routes: &[RouteObject]) -> Value {
+    let mut paths: Map<String, Value> = Map::new();
+    let mut components: Map<String, Value> = Map::new();
+
+    for route_object in routes {
+        let method: String = route_object.method.to_lowercase();
+        let resource: String = resource_name(&route_object.route);
+        let mut operation: Map<String, Value> = Map::new();
+        operation.insert(
+            "operationId".to_string(),
+            json!(operation_id(&method, &route_object.route)),
+        );
+        let parameters: Vec<Value> = path_parameters(&route_object.route);
+        if !parameters.is_empty() {
+            operation.insert("parameters".to_string(), Value::Array(parameters));
+        }
+        if !is_no_body(&route_object.request_body) {
+            let schema: Value = component_ref(
+                &mut components,
+                &resource,
+                infer_schema(&route_object.request_body),
+            );
+            operation.insert(
+                "requestBody".to_string(),
+                json!({ "required": true, "content": { "application/json": { "schema": schema } } }),
+            );
+        }
+        let response: Value = if is_no_body(&route_object.response) {
+            json!({ "description": "OK" })
+        } else {
+            let schema: Value = component_ref(
+                &mut components,
+                &resource,
+                infer_schema(&route_object.response),
+            );
+            json!({ "description": "OK", "content": { "application/json": { "schema": schema } } })
+        };
+        operation.insert("responses".to_string(), json!({ "200": response }));
+
+        let path_item: &mut Value = paths
+            .entry(route_object.route.clone())
+            .or_insert_with(|| json!({}));
+        path_item[method] = Value::Object(operation);
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "BackendBro generated backend",
+            "version": "0.1.0",
+            "description": description.trim(),
+        },
+        "paths": paths,
+        "components": { "schemas": components },
+    })
+}
+
+pub fn save_openapi_document(project_dir: &Path, description: &str, routes: &[RouteObject]) {
+    let document: Value = openapi_document(description, routes);
+    fs::write(
+        project_dir.join(OPENAPI_PATH),
+        serde_json::to_string_pretty(&document).unwrap(),
+    )
+    .expect("Couldn't write to file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_converts_endpoint_schema() {
+        let schema: String = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/schemas/api_schema.json"
+        ))
+        .unwrap();
+        let routes: Vec<RouteObject> = serde_json::from_str(&schema).unwrap();
+        let document: Value = openapi_document("a song catalogue", &routes);
+
+        assert_eq!(document["openapi"], "3.1.0");
+        let song_ref: Value = json!({ "$ref": "#/components/schemas/Song" });
+        assert_eq!(
+            document["paths"]["/song"]["post"]["requestBody"]["content"]["application/json"]["schema"],
+            song_ref
+        );
+        assert_eq!(
+            document["paths"]["/song/{id}"]["get"]["responses"]["200"]["content"]["application/json"]
+                ["schema"],
+            song_ref
+        );
+        assert_eq!(
+            document["paths"]["/song/{id}"]["delete"]["parameters"][0],
+            json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } })
+        );
+        let song: &Value = &document["components"]["schemas"]["Song"];
+        assert_eq!(song["properties"]["rank"], json!({ "type": "number" }));
+        assert_eq!(song["required"].as_array().unwrap().len(), 4);
+        assert_eq!(
+            document["components"]["schemas"].as_object().unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn tests_infers_nested_types() {
+        let body: Value =
+            json!([{ "tags": "Vec<String>", "note": "Option<String>", "done": "bool" }]);
+        assert_eq!(
+            infer_schema(&body),
+            json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "done": { "type": "boolean" },
+                        "note": { "type": ["string", "null"] },
+                        "tags": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["done", "tags"]
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::helpers::command_line::PrintCommand;
+use crate::models::agents::agents_traits::{FactSheet, FixReport};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+pub const FIX_REPORT_FILE: &str = "fix_report.json";
+// Copy of main.rs from the last build that compiled
+pub const LAST_COMPILING_FILE: &str = "last_compiling_main.rs";
+
+// Exit statuses of a run, 2 is left to clap for bad arguments
+pub const EXIT_FAILED: u8 = 1;
+pub const EXIT_BUDGET_EXHAUSTED: u8 = 3;
+pub const EXIT_CANCELLED: u8 = 130;
+
+// An agent used up its fix attempts, the report is on disk
+#[derive(Debug)]
+pub struct BudgetExhausted {
+    pub agent: String,
+    pub report_path: PathBuf,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} used up its fix budget, see {}",
+            self.agent,
+            self.report_path.display()
+        )
+    }
+}
+
+impl Error for BudgetExhausted {}
+
+// Writes the report and the last compiling main.rs next to it, returns the report as written
+pub fn write_fix_report(
+    project_dir: &Path,
+    mut report: FixReport,
+    last_compiling_code: Option<&str>,
+) -> io::Result<FixReport> {
+    if let Some(code) = last_compiling_code {
+        let revision: PathBuf = project_dir.join(LAST_COMPILING_FILE);
+        fs::write(&revision, code)?;
+        report.last_compiling_revision = Some(revision);
+    }
+    let contents: String = serde_json::to_string_pretty(&report).map_err(io::Error::from)?;
+    fs::write(project_dir.join(FIX_REPORT_FILE), contents)?;
+    Ok(report)
+}
+
+// Out of fix attempts: writes the report, keeps it in the FactSheet and returns the error the agent
+// stops with
+pub fn give_up(
+    factsheet: &mut FactSheet,
+    report: FixReport,
+    last_compiling_code: Option<&str>,
+) -> io::Result<BudgetExhausted> {
+    let report: FixReport =
+        write_fix_report(factsheet.project_path(), report, last_compiling_code)?;
+    let revision: String = match &report.last_compiling_revision {
+        Some(path) => format!("last compiling revision in {}", path.display()),
+        None => "no revision compiled".to_string(),
+    };
+    PrintCommand::Issue.print_agent_message(
+        report.agent.as_str(),
+        &format!(
+            "Fix budget of {} used up after {} attempts, {}",
+            report.bug_budget,
+            report.attempts.len(),
+            revision
+        ),
+    );
+    let exhausted: BudgetExhausted = BudgetExhausted {
+        agent: report.agent.clone(),
+        report_path: factsheet.project_path().join(FIX_REPORT_FILE),
+    };
+    factsheet.fix_report = Some(report);
+    Ok(exhausted)
+}
+
+// Exit status for the error a run stopped with
+pub fn exit_code(err: &(dyn Error + 'static)) -> u8 {
+    if err.is::<BudgetExhausted>() {
+        EXIT_BUDGET_EXHAUSTED
+    } else if err
+        .downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == ErrorKind::Interrupted)
+    {
+        EXIT_CANCELLED
+    } else {
+        EXIT_FAILED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agents::agents_traits::FixAttempt;
+
+    #[test]
+    fn tests_writes_fix_report() {
+        let project_dir: PathBuf =
+            std::env::temp_dir().join(format!("backendbro-outcome-{}", std::process::id()));
+        fs::create_dir_all(&project_dir).unwrap();
+        let report: FixReport = FixReport {
+            agent: "Backend Developer".to_string(),
+            bug_budget: 1,
+            last_compiling_revision: None,
+            remaining_errors: "error[E0308] src/main.rs:4: mismatched types".to_string(),
+            attempts: vec![FixAttempt {
+                attempt: 1,
+                stage: "clippy".to_string(),
+                errors: 0,
+                warnings: 12,
+                diagnostics: "12 clippy warnings, at most 10 allowed".to_string(),
+            }],
+        };
+
+        let written: FixReport =
+            write_fix_report(&project_dir, report, Some("fn main() {}\n")).unwrap();
+        let revision: PathBuf = project_dir.join(LAST_COMPILING_FILE);
+        assert_eq!(written.last_compiling_revision.as_ref(), Some(&revision));
+        assert_eq!(fs::read_to_string(revision).unwrap(), "fn main() {}\n");
+        let saved: FixReport =
+            serde_json::from_str(&fs::read_to_string(project_dir.join(FIX_REPORT_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(saved, written);
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+
+    #[test]
+    fn tests_give_up_records_report() {
+        let project_dir: PathBuf =
+            std::env::temp_dir().join(format!("backendbro-give-up-{}", std::process::id()));
+        fs::create_dir_all(&project_dir).unwrap();
+        let mut factsheet: FactSheet = FactSheet {
+            project_dir: Some(project_dir.clone()),
+            ..Default::default()
+        };
+        let report: FixReport = FixReport {
+            agent: "Test Engineer".to_string(),
+            bug_budget: 0,
+            last_compiling_revision: None,
+            remaining_errors: "test songs ... FAILED".to_string(),
+            attempts: vec![],
+        };
+
+        let exhausted: BudgetExhausted = give_up(&mut factsheet, report, None).unwrap();
+        assert_eq!(exhausted.agent, "Test Engineer");
+        assert!(exhausted.report_path.is_file());
+        assert_eq!(
+            factsheet.fix_report.map(|report| report.remaining_errors),
+            Some("test songs ... FAILED".to_string())
+        );
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+
+    #[test]
+    fn tests_exit_codes() {
+        let exhausted: Box<dyn Error> = Box::new(BudgetExhausted {
+            agent: "Backend Developer".to_string(),
+            report_path: PathBuf::from(FIX_REPORT_FILE),
+        });
+        let cancelled: Box<dyn Error> =
+            Box::new(io::Error::new(ErrorKind::Interrupted, "Run cancelled"));
+        assert_eq!(exit_code(exhausted.as_ref()), EXIT_BUDGET_EXHAUSTED);
+        assert_eq!(exit_code(cancelled.as_ref()), EXIT_CANCELLED);
+        assert_eq!(
+            exit_code(Box::<dyn Error>::from("bad schema").as_ref()),
+            EXIT_FAILED
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{
+    EXEC_MAIN_PATH, ai_task_request, extract_code_block, read_exec_main_contents,
+};
+use std::fs;
+use std::path::Path;
+
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+// Replace the one place `search` occurs with `replace`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub search: String,
+    pub replace: String,
+}
+
+// SEARCH/REPLACE blocks of a model response, anything around them is ignored
+pub fn parse_edits(response: &str) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = vec![];
+    let mut lines = response.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() != SEARCH_MARKER {
+            continue;
+        }
+        let search: Vec<&str> = lines
+            .by_ref()
+            .take_while(|line| line.trim() != DIVIDER_MARKER)
+            .collect();
+        let replace: Vec<&str> = lines
+            .by_ref()
+            .take_while(|line| line.trim() != REPLACE_MARKER)
+            .collect();
+        edits.push(Edit {
+            search: search.join("\n"),
+            replace: replace.join("\n"),
+        });
+    }
+    edits
+}
+
+// Applies the edits in order, each has to match exactly one place of the code as it is by then.
+// Nothing is applied when one of them conflicts
+pub fn apply_edits(source: &str, edits: &[Edit]) -> Result<String, String> {
+    if edits.is_empty() {
+        return Err("no SEARCH/REPLACE edits in the response".to_string());
+    }
+    let mut code: String = source.to_string();
+    for (index, edit) in edits.iter().enumerate() {
+        if edit.search.trim().is_empty() {
+            return Err(format!("edit {} has an empty SEARCH block", index + 1));
+        }
+        match code.matches(edit.search.as_str()).count() {
+            1 => code = code.replacen(edit.search.as_str(), &edit.replace, 1),
+            0 => return Err(format!("SEARCH block of edit {} not found", index + 1)),
+            count => {
+                return Err(format!(
+                    "SEARCH block of edit {} matches {} places",
+                    index + 1,
+                    count
+                ));
+            }
+        }
+    }
+    Ok(code)
+}
+
+// Code of a regenerated main.rs, it has to be fenced and parse as a Rust file
+pub fn regenerated_code(ai_response: &str) -> Result<String, String> {
+    let code: String = extract_code_block(ai_response.to_string())
+        .ok_or_else(|| "no code block in the response".to_string())?;
+    syn::parse_file(&code).map_err(|e| format!("not valid Rust: {}", e))?;
+    Ok(code)
+}
+
+// Asks for edits of main.rs and applies them, the whole file is asked for again when they don't
+// apply. Returns the code now in main.rs, which stays as it was when neither is usable
+pub async fn patch_backend_code(
+    project_dir: &Path,
+    msg_context: String,
+    agent_position: &str,
+    patch: (&str, fn(&str) -> &'static str),
+    regenerate: (&str, fn(&str) -> &'static str),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (patch_operation, patch_function) = patch;
+    let ai_response: String = ai_task_request(
+        msg_context.clone(),
+        agent_position,
+        patch_operation,
+        patch_function,
+    )
+    .await?;
+    let edits: Vec<Edit> = parse_edits(&ai_response);
+    let current_code: String = read_exec_main_contents(project_dir);
+    match apply_edits(&current_code, &edits) {
+        Ok(code) => {
+            fs::write(project_dir.join(EXEC_MAIN_PATH), &code)?;
+            PrintCommand::AICall.print_agent_message(
+                agent_position,
+                &format!("Applied {} edits to main.rs", edits.len()),
+            );
+            Ok(code)
+        }
+        Err(conflict) => {
+            PrintCommand::Issue.print_agent_message(
+                agent_position,
+                &format!("Patch did not apply ({}), regenerating main.rs", conflict),
+            );
+            let (regenerate_operation, regenerate_function) = regenerate;
+            let ai_response: String = ai_task_request(
+                msg_context,
+                agent_position,
+                regenerate_operation,
+                regenerate_function,
+            )
+            .await?;
+            match regenerated_code(&ai_response) {
+                Ok(code) => {
+                    fs::write(project_dir.join(EXEC_MAIN_PATH), &code)?;
+                    Ok(code)
+                }
+                Err(problem) => {
+                    PrintCommand::Issue.print_agent_message(
+                        agent_position,
+                        &format!(
+                            "Regenerated main.rs is unusable ({}), keeping it as it was",
+                            problem
+                        ),
+                    );
+                    Ok(current_code)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "fn main() {\n    let port = 8080;\n    println!(\"{}\", port);\n}\n";
+
+    #[test]
+    fn tests_applies_search_replace_edits() {
+        let response: &str = "Here is the fix:\n```\n<<<<<<< SEARCH\n    let port = 8080;\n=======\n    let port: u16 = 8080;\n>>>>>>> REPLACE\n<<<<<<< SEARCH\n    println!(\"{}\", port);\n=======\n>>>>>>> REPLACE\n```";
+        let edits: Vec<Edit> = parse_edits(response);
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].replace, "");
+        assert_eq!(
+            apply_edits(CODE, &edits).unwrap(),
+            "fn main() {\n    let port: u16 = 8080;\n\n}\n"
+        );
+    }
+
+    #[test]
+    fn tests_rejects_conflicting_edits() {
+        let missing: Edit = Edit {
+            search: "let port = 3000;".to_string(),
+            replace: "let port = 8080;".to_string(),
+        };
+        let ambiguous: Edit = Edit {
+            search: "port".to_string(),
+            replace: "addr".to_string(),
+        };
+        assert_eq!(
+            apply_edits(CODE, &[missing]),
+            Err("SEARCH block of edit 1 not found".to_string())
+        );
+        assert_eq!(
+            apply_edits(CODE, &[ambiguous]),
+            Err("SEARCH block of edit 1 matches 2 places".to_string())
+        );
+        assert!(apply_edits(CODE, &[]).is_err());
+    }
+
+    #[test]
+    fn tests_checks_regenerated_code() {
+        assert_eq!(
+            regenerated_code(&format!("```rust\n{}```", CODE)),
+            Ok(format!("\n{}", CODE))
+        );
+        assert_eq!(
+            regenerated_code("AI service unavailable"),
+            Err("no code block in the response".to_string())
+        );
+        assert!(
+            regenerated_code("```rust\nfn main() {\n```")
+                .unwrap_err()
+                .starts_with("not valid Rust")
+        );
+    }
+}
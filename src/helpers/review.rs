@@ -0,0 +1,198 @@
+use crate::helpers::command_line::{PrintCommand, get_user_response};
+use crate::models::agents::agents_traits::{FactSheet, ProjectScope};
+use reqwest::Url;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const REVIEW_FILE: &str = "factsheet.review.json";
+
+// Lets a human correct the FactSheet before it is handed to the next agent
+pub fn review_factsheet(factsheet: &FactSheet, next_agent: &str) -> FactSheet {
+    let review_msg: String = format!("Review the FactSheet before {} starts", next_agent);
+    PrintCommand::UnitTest.print_agent_message("Human Review", review_msg.as_str());
+
+    match env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+        Ok(editor) if !editor.trim().is_empty() => review_in_editor(factsheet, &editor),
+        _ => review_field_by_field(factsheet),
+    }
+}
+
+// Checks an edited FactSheet is still something the agents can work with
+pub fn validate_factsheet(edited: &FactSheet, original: &FactSheet) -> Result<(), String> {
+    if edited.project_description.trim().is_empty() {
+        return Err("project_description must not be empty".to_string());
+    }
+    if edited.project_dir != original.project_dir {
+        return Err("project_dir cannot be changed during review".to_string());
+    }
+    if let Some(urls) = &edited.external_urls {
+        for url in urls {
+            match Url::parse(url) {
+                Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+                _ => {
+                    return Err(format!(
+                        "external_urls entry '{}' is not an http(s) url",
+                        url
+                    ));
+                }
+            }
+        }
+    }
+    if original.project_scope.is_some() && edited.project_scope.is_none() {
+        return Err("project_scope cannot be removed once determined".to_string());
+    }
+    Ok(())
+}
+
+fn review_in_editor(factsheet: &FactSheet, editor: &str) -> FactSheet {
+    let review_path: PathBuf = match &factsheet.project_dir {
+        Some(project_dir) => project_dir.join(REVIEW_FILE),
+        None => env::temp_dir().join(REVIEW_FILE),
+    };
+    fs::write(
+        &review_path,
+        serde_json::to_string_pretty(factsheet).expect("Failed to serialize FactSheet"),
+    )
+    .expect("Failed to write FactSheet for review");
+
+    loop {
+        // Editors like "code --wait" come with their own arguments
+        let mut editor_args = editor.split_whitespace();
+        let program: &str = editor_args.next().unwrap_or("vi");
+        let status = Command::new(program)
+            .args(editor_args)
+            .arg(&review_path)
+            .status();
+
+        let edited: Result<FactSheet, String> = match status {
+            Ok(status) if status.success() => fs::read_to_string(&review_path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    serde_json::from_str::<FactSheet>(&contents).map_err(|e| e.to_string())
+                })
+                .and_then(|edited| validate_factsheet(&edited, factsheet).map(|_| edited)),
+            Ok(status) => Err(format!("{} exited with {}", program, status)),
+            Err(e) => Err(format!("Failed to launch {}: {}", program, e)),
+        };
+
+        match edited {
+            Ok(edited) => {
+                let _ = fs::remove_file(&review_path);
+                return edited;
+            }
+            Err(err) => {
+                let err_msg: String = format!("Edited FactSheet rejected: {}", err);
+                PrintCommand::Issue.print_agent_message("Human Review", err_msg.as_str());
+                let retry: String = get_user_response("Edit again? [y/N] (N keeps the original)");
+                if !matches!(retry.to_lowercase().as_str(), "y" | "yes") {
+                    let _ = fs::remove_file(&review_path);
+                    return factsheet.clone();
+                }
+            }
+        }
+    }
+}
+
+fn ask_bool(question: &str, current: bool) -> bool {
+    loop {
+        let answer: String = get_user_response(&format!("{} [{}]", question, current));
+        match answer.to_lowercase().as_str() {
+            "" => return current,
+            "true" | "y" | "yes" => return true,
+            "false" | "n" | "no" => return false,
+            _ => println!("Invalid Input. Please answer 'y' or 'n'"),
+        }
+    }
+}
+
+fn review_field_by_field(factsheet: &FactSheet) -> FactSheet {
+    println!("No $EDITOR set, reviewing field by field. Press enter to keep a value.");
+    let mut edited: FactSheet = factsheet.clone();
+
+    println!("project_description: {}", factsheet.project_description);
+    let description: String = get_user_response("New project_description:");
+    if !description.is_empty() {
+        edited.project_description = description;
+    }
+
+    if let Some(scope) = &factsheet.project_scope {
+        edited.project_scope = Some(ProjectScope {
+            is_crud_required: ask_bool("is_crud_required?", scope.is_crud_required),
+            is_user_login_and_logout: ask_bool(
+                "is_user_login_and_logout?",
+                scope.is_user_login_and_logout,
+            ),
+            is_external_urls_required: ask_bool(
+                "is_external_urls_required?",
+                scope.is_external_urls_required,
+            ),
+        });
+    }
+
+    if let Some(urls) = &factsheet.external_urls {
+        let kept: Vec<String> = urls
+            .iter()
+            .filter(|url| ask_bool(&format!("Keep external url {}?", url), true))
+            .cloned()
+            .collect();
+        edited.external_urls = Some(kept);
+    }
+    let extra_urls: String = get_user_response("Extra external urls (comma separated):");
+    if !extra_urls.is_empty() {
+        edited
+            .external_urls
+            .get_or_insert_with(Vec::new)
+            .extend(extra_urls.split(',').map(|url| url.trim().to_string()));
+    }
+
+    match validate_factsheet(&edited, factsheet) {
+        Ok(()) => edited,
+        Err(err) => {
+            let err_msg: String = format!("Edited FactSheet rejected, keeping original: {}", err);
+            PrintCommand::Issue.print_agent_message("Human Review", err_msg.as_str());
+            factsheet.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factsheet() -> FactSheet {
+        FactSheet {
+            project_description: "build a website that shows crypto prices".to_string(),
+            project_scope: Some(ProjectScope {
+                is_crud_required: false,
+                is_user_login_and_logout: false,
+                is_external_urls_required: true,
+            }),
+            external_urls: Some(vec![
+                "https://api.binance.com/api/v3/exchangeInfo".to_string(),
+            ]),
+            project_dir: Some(PathBuf::from("generated/backend-1")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tests_validates_edited_factsheet() {
+        let original: FactSheet = factsheet();
+        let mut edited: FactSheet = original.clone();
+        edited.external_urls = Some(vec![]);
+        assert!(validate_factsheet(&edited, &original).is_ok());
+
+        edited.external_urls = Some(vec!["api.binance.com".to_string()]);
+        assert!(validate_factsheet(&edited, &original).is_err());
+
+        let mut edited: FactSheet = original.clone();
+        edited.project_description = " ".to_string();
+        assert!(validate_factsheet(&edited, &original).is_err());
+
+        let mut edited: FactSheet = original.clone();
+        edited.project_dir = None;
+        assert!(validate_factsheet(&edited, &original).is_err());
+    }
+}
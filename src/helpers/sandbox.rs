@@ -0,0 +1,408 @@
+use crate::helpers::general::copy_dir_all;
+use crate::helpers::server::SERVER_HOST;
+use crate::models::agents::agent_database::MIGRATIONS_DIR;
+use std::io::{self, Error, ErrorKind};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use std::{env, fs, thread};
+
+// Limits of the generated server, generous for a dev server but they stop runaway code
+pub const SANDBOX_CPU_SECS: u64 = 600;
+pub const SANDBOX_MEMORY_BYTES: u64 = 4 << 30;
+pub const SANDBOX_MAX_PROCESSES: u64 = 4096;
+pub const SANDBOX_MAX_FILE_BYTES: u64 = 1 << 30;
+
+// Which limits a sandboxed command gets. Cargo runs rustc, whose address space and parallel jobs
+// would hit the memory and process limits, so it only gets the per process CPU, file size and core
+// limits. Test binaries it starts inherit those
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Limits {
+    Server,
+    Cargo,
+}
+
+// Files the server reads relative to its working dir
+const SERVER_ASSETS: [&str; 2] = ["static", MIGRATIONS_DIR];
+
+static SANDBOX_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// Cargo, rustup, proxy and locale settings, API keys and the rest of the environment stay out
+pub fn is_build_var(name: &str) -> bool {
+    matches!(
+        name,
+        "PATH" | "HOME" | "USER" | "LANG" | "TERM" | "TMPDIR" | "SSL_CERT_FILE" | "SSL_CERT_DIR"
+    ) || ["CARGO", "RUSTUP_", "RUSTC", "RUSTFLAGS", "RUSTDOC", "LC_"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || name.to_lowercase().ends_with("_proxy")
+}
+
+// Builds and tests of AI written code only see what cargo needs
+pub fn scrub_build_env(command: &mut Command) {
+    command.env_clear();
+    command.envs(env::vars_os().filter(|(name, _)| name.to_str().is_some_and(is_build_var)));
+}
+
+// Private working dir of one server run, removed when the server is done
+#[derive(Debug)]
+pub struct Sandbox {
+    pub work_dir: PathBuf,
+    pub loopback_only: bool,
+    // Isolation that was asked for or expected but is not available here
+    pub gaps: Vec<String>,
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+fn private_dir() -> io::Result<PathBuf> {
+    let work_dir: PathBuf = env::temp_dir().join(format!(
+        "backendbro-sandbox-{}-{}",
+        std::process::id(),
+        SANDBOX_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&work_dir);
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(&work_dir)?;
+    Ok(work_dir)
+}
+
+// Private dir with the server's assets, network isolation only where namespaces can be entered
+pub fn prepare_sandbox(project_dir: &Path, isolate_network: bool) -> io::Result<Sandbox> {
+    // Only the working dir is private, absolute paths still reach the user's files
+    let mut gaps: Vec<String> = vec![
+        "no filesystem isolation, the server can read files such as .env by absolute path"
+            .to_string(),
+    ];
+    if !cfg!(unix) {
+        gaps.push("no CPU, memory or process limits outside unix".to_string());
+    }
+    let namespace_gap: Option<String> = isolate_network.then(network_namespace_gap).flatten();
+    let loopback_only: bool = isolate_network && namespace_gap.is_none();
+    gaps.extend(namespace_gap);
+
+    let work_dir: PathBuf = private_dir()?;
+    let sandbox: Sandbox = Sandbox {
+        work_dir,
+        loopback_only,
+        gaps,
+    };
+    for asset in SERVER_ASSETS {
+        let source: PathBuf = project_dir.join(asset);
+        if source.is_dir() {
+            copy_dir_all(&source, &sandbox.work_dir.join(asset))?;
+        }
+    }
+    Ok(sandbox)
+}
+
+// Why the server can't get a loopback-only network namespace, if it can't
+fn network_namespace_gap() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        // Reaching into the server's namespace takes CAP_SYS_ADMIN in our own namespace
+        if unsafe { libc::geteuid() } != 0 {
+            return Some(
+                "loopback-only network needs root to reach the server inside its namespace, the server shares the host network"
+                    .to_string(),
+            );
+        }
+        None
+    }
+    #[cfg(not(target_os = "linux"))]
+    Some(
+        "loopback-only network needs Linux namespaces, the server shares the host network"
+            .to_string(),
+    )
+}
+
+// Cargo and rustup keep using the user's toolchains and registry once HOME is moved
+fn toolchain_dir(var: &str, default_dir: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .or_else(|| env::home_dir().map(|home| home.join(default_dir)))
+}
+
+// Cargo run on AI written code, such as its tests, under the cargo limits with a private HOME
+// and TMPDIR. The sandbox has to outlive the command
+pub fn sandboxed_cargo(args: &[&str], project_dir: &Path) -> io::Result<(Command, Sandbox)> {
+    let sandbox: Sandbox = Sandbox {
+        work_dir: private_dir()?,
+        loopback_only: false,
+        gaps: vec![],
+    };
+    let mut command: Command = Command::new("cargo");
+    command.args(args).current_dir(project_dir);
+    scrub_build_env(&mut command);
+    for (var, default_dir) in [("CARGO_HOME", ".cargo"), ("RUSTUP_HOME", ".rustup")] {
+        if let Some(dir) = toolchain_dir(var, default_dir) {
+            command.env(var, dir);
+        }
+    }
+    command
+        .env("HOME", &sandbox.work_dir)
+        .env("TMPDIR", &sandbox.work_dir);
+    #[cfg(unix)]
+    apply_limits(&mut command, Limits::Cargo, false);
+    Ok((command, sandbox))
+}
+
+// Runs the server binary with a cleared environment in the sandbox dir, under rlimits
+pub fn sandboxed_command(binary: &Path, sandbox: &Sandbox) -> io::Result<Command> {
+    // A relative program path would be resolved against the new working dir
+    let mut command: Command = Command::new(binary.canonicalize()?);
+    command
+        .env_clear()
+        .env("PATH", "/usr/local/bin:/usr/bin:/bin")
+        .env("HOME", &sandbox.work_dir)
+        .env("TMPDIR", &sandbox.work_dir)
+        .env("RUST_BACKTRACE", "1")
+        .current_dir(&sandbox.work_dir);
+    #[cfg(unix)]
+    apply_limits(&mut command, Limits::Server, sandbox.loopback_only);
+    Ok(command)
+}
+
+// Limit capped at the current hard limit, raising it would fail
+#[cfg(unix)]
+fn capped_limit(current: libc::rlimit, wanted: u64) -> libc::rlimit {
+    let value: libc::rlim_t = (wanted as libc::rlim_t).min(current.rlim_max);
+    libc::rlimit {
+        rlim_cur: value,
+        rlim_max: value,
+    }
+}
+
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, kind: Limits, loopback_only: bool) {
+    use std::os::unix::process::CommandExt;
+    let limits: Vec<_> = [
+        (libc::RLIMIT_CPU, SANDBOX_CPU_SECS),
+        (libc::RLIMIT_AS, SANDBOX_MEMORY_BYTES),
+        (libc::RLIMIT_NPROC, SANDBOX_MAX_PROCESSES),
+        (libc::RLIMIT_FSIZE, SANDBOX_MAX_FILE_BYTES),
+        (libc::RLIMIT_CORE, 0),
+    ]
+    .into_iter()
+    .filter(|(resource, _)| {
+        kind == Limits::Server || (*resource != libc::RLIMIT_AS && *resource != libc::RLIMIT_NPROC)
+    })
+    .map(|(resource, wanted)| {
+        let mut current: libc::rlimit = libc::rlimit {
+            rlim_cur: libc::RLIM_INFINITY,
+            rlim_max: libc::RLIM_INFINITY,
+        };
+        unsafe { libc::getrlimit(resource, &mut current) };
+        (resource, capped_limit(current, wanted))
+    })
+    .collect();
+    // Runs in the forked child before exec, so only plain system calls
+    unsafe {
+        command.pre_exec(move || {
+            for (resource, limit) in &limits {
+                if libc::setrlimit(*resource, limit) != 0 {
+                    return Err(Error::last_os_error());
+                }
+            }
+            #[cfg(target_os = "linux")]
+            if loopback_only {
+                enter_loopback_namespace()?;
+            }
+            #[cfg(not(target_os = "linux"))]
+            let _ = loopback_only;
+            Ok(())
+        });
+    }
+}
+
+// New network namespace with only the loopback interface, brought up so the server can bind it
+#[cfg(target_os = "linux")]
+unsafe fn enter_loopback_namespace() -> io::Result<()> {
+    unsafe {
+        if libc::unshare(libc::CLONE_NEWNET) != 0 {
+            return Err(Error::last_os_error());
+        }
+        let socket: libc::c_int =
+            libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+        if socket < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut request: libc::ifreq = std::mem::zeroed();
+        request.ifr_name[0] = b'l' as libc::c_char;
+        request.ifr_name[1] = b'o' as libc::c_char;
+        request.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+        let result: libc::c_int = libc::ioctl(socket, libc::SIOCSIFFLAGS as _, &request);
+        libc::close(socket);
+        if result != 0 {
+            return Err(Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// Connects to a port inside the network namespace of pid, from a thread that joins it
+pub fn connect_in_namespace(pid: u32, port: u16) -> io::Result<TcpStream> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+        thread::spawn(move || {
+            let namespace: fs::File = fs::File::open(format!("/proc/{}/ns/net", pid))?;
+            if unsafe { libc::setns(namespace.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+                return Err(Error::last_os_error());
+            }
+            TcpStream::connect((SERVER_HOST, port))
+        })
+        .join()
+        .map_err(|_| Error::other("Namespace thread panicked"))?
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (pid, port);
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Network namespaces need Linux",
+        ))
+    }
+}
+
+// Relays connections on a host port into the server's namespace, the only way in once it is cut off
+#[derive(Debug)]
+pub struct NamespaceForwarder {
+    pub port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl NamespaceForwarder {
+    pub fn start(pid: u32, target_port: u16) -> io::Result<Self> {
+        let listener: TcpListener = TcpListener::bind((SERVER_HOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let port: u16 = listener.local_addr()?.port();
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let stopped: Arc<AtomicBool> = stop.clone();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((client, _)) => {
+                        thread::spawn(move || relay(client, pid, target_port));
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(20));
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Self { port, stop })
+    }
+}
+
+impl Drop for NamespaceForwarder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn relay(client: TcpStream, pid: u32, target_port: u16) -> io::Result<()> {
+    client.set_nonblocking(false)?;
+    let server: TcpStream = connect_in_namespace(pid, target_port)?;
+    let (mut client_read, mut server_write) = (client.try_clone()?, server.try_clone()?);
+    let upstream = thread::spawn(move || {
+        let _ = io::copy(&mut client_read, &mut server_write);
+        let _ = server_write.shutdown(Shutdown::Write);
+    });
+    let (mut server_read, mut client_write) = (server, client);
+    let _ = io::copy(&mut server_read, &mut client_write);
+    let _ = client_write.shutdown(Shutdown::Write);
+    let _ = upstream.join();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_keeps_secrets_out_of_builds() {
+        assert!(!is_build_var("GEMINI_API_KEY"));
+        assert!(!is_build_var("AWS_SECRET_ACCESS_KEY"));
+        assert!(is_build_var("PATH"));
+        assert!(is_build_var("CARGO_HOME"));
+        assert!(is_build_var("RUSTUP_TOOLCHAIN"));
+        assert!(is_build_var("https_proxy"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tests_sandboxed_command_clears_environment() {
+        let project_dir: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("web_template");
+        let sandbox: Sandbox = prepare_sandbox(&project_dir, false).unwrap();
+        let work_dir: PathBuf = sandbox.work_dir.clone();
+        let mut command: Command = sandboxed_command(Path::new("/bin/sh"), &sandbox).unwrap();
+        // cargo sets CARGO_MANIFEST_DIR for the test process, the server must not see it
+        command.args(["-c", "echo $CARGO_MANIFEST_DIR; pwd; ulimit -t"]);
+        let output = command.output().unwrap();
+        let stdout: String = String::from_utf8_lossy(&output.stdout).to_string();
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[0], "");
+        assert_eq!(Path::new(lines[1]), work_dir.canonicalize().unwrap());
+        assert_eq!(lines[2], SANDBOX_CPU_SECS.to_string());
+
+        assert!(sandbox.gaps[0].starts_with("no filesystem isolation"));
+
+        drop(sandbox);
+        assert!(!work_dir.exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tests_cargo_keeps_memory_and_process_limits() {
+        let ulimits = |limits: Option<Limits>| {
+            let mut command: Command = Command::new("/bin/sh");
+            command.args(["-c", "ulimit -t; ulimit -v; ulimit -u"]);
+            if let Some(kind) = limits {
+                apply_limits(&mut command, kind, false);
+            }
+            String::from_utf8_lossy(&command.output().unwrap().stdout).to_string()
+        };
+        let unlimited: String = ulimits(None);
+        let cargo: String = ulimits(Some(Limits::Cargo));
+        let cargo_lines: Vec<&str> = cargo.lines().collect();
+        // Only the CPU time is cut, rustc keeps the memory and processes it had
+        assert_eq!(cargo_lines[0], SANDBOX_CPU_SECS.to_string());
+        assert_eq!(
+            cargo_lines[1..],
+            unlimited.lines().collect::<Vec<&str>>()[1..]
+        );
+    }
+
+    #[test]
+    fn tests_sandboxed_cargo_moves_home() {
+        let project_dir: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("web_template");
+        let (command, sandbox) = sandboxed_cargo(&["test"], &project_dir).unwrap();
+        let env_value = |name: &str| {
+            command
+                .get_envs()
+                .find(|(var, _)| *var == name)
+                .and_then(|(_, value)| value)
+                .map(PathBuf::from)
+        };
+        assert_eq!(env_value("HOME"), Some(sandbox.work_dir.clone()));
+        assert_eq!(env_value("TMPDIR"), Some(sandbox.work_dir.clone()));
+        assert!(env_value("CARGO_HOME").is_some());
+        assert!(env_value("GEMINI_API_KEY").is_none());
+        assert_eq!(command.get_current_dir(), Some(project_dir.as_path()));
+    }
+}
@@ -0,0 +1,395 @@
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::config::run_config;
+use crate::helpers::diagnostics::{Diagnostic, apply_suggestions, parse_cargo_messages};
+use crate::helpers::openapi::{infer_schema, is_no_body};
+use crate::helpers::process::{ProcessGuard, run_with_timeout};
+use crate::helpers::sandbox::{
+    NamespaceForwarder, Sandbox, connect_in_namespace, prepare_sandbox, sandboxed_cargo,
+    sandboxed_command, scrub_build_env,
+};
+use reqwest::{Client, Method};
+use serde_json::{Map, Value, json};
+use std::io::{Error, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::time::{Duration, Instant};
+use std::{fs, io};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+pub const SERVER_HOST: &str = "127.0.0.1";
+pub const DEFAULT_SERVER_PORT: u16 = 8080;
+// Environment variables the generated server reads its address from
+pub const PORT_ENV: &str = "PORT";
+pub const HOST_ENV: &str = "HOST";
+pub const SERVER_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Rebuilds with rustc's own fixes applied before a failed build goes to the model
+const MAX_SUGGESTION_ROUNDS: usize = 3;
+
+// Port of the server started last, the routes are called on it
+static SERVER_PORT: AtomicU16 = AtomicU16::new(DEFAULT_SERVER_PORT);
+static ISOLATION_REPORTED: AtomicBool = AtomicBool::new(false);
+
+// A generated server running in its sandbox, dropping it stops the server and cleans up
+#[derive(Debug)]
+// Fields drop in order: the server is killed before its forwarder and dir go away
+pub struct RunningServer {
+    _process: ProcessGuard,
+    _forwarder: Option<NamespaceForwarder>,
+    _sandbox: Sandbox,
+}
+
+// Builds the generated project within the configured build timeout, compiler messages come as
+// JSON lines on stdout
+pub async fn build_project(
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> std::io::Result<Output> {
+    let mut build_command: Command = Command::new("cargo");
+    build_command
+        .args(["build", "--message-format=json"])
+        .current_dir(project_dir);
+    scrub_build_env(&mut build_command);
+    run_with_timeout(build_command, run_config().build_timeout, cancel).await
+}
+
+// Last build of the project and how many compiler suggestions were applied on the way
+pub struct FixedBuild {
+    pub output: Output,
+    pub diagnostics: Vec<Diagnostic>,
+    pub applied: usize,
+}
+
+// Builds the project, and while it fails applies rustc's machine-applicable suggestions and builds
+// again, so only the errors left need the model
+pub async fn build_applying_suggestions(
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> io::Result<FixedBuild> {
+    let mut applied: usize = 0;
+    let mut round: usize = 0;
+    loop {
+        let output: Output = build_project(project_dir, cancel).await?;
+        let diagnostics: Vec<Diagnostic> =
+            parse_cargo_messages(&String::from_utf8_lossy(&output.stdout));
+        if output.status.success() || round == MAX_SUGGESTION_ROUNDS {
+            return Ok(FixedBuild {
+                output,
+                diagnostics,
+                applied,
+            });
+        }
+        let applied_now: usize = apply_suggestions(&diagnostics, project_dir)?;
+        if applied_now == 0 {
+            return Ok(FixedBuild {
+                output,
+                diagnostics,
+                applied,
+            });
+        }
+        applied += applied_now;
+        round += 1;
+    }
+}
+
+// Release build, the one the Dockerfile copies into the image
+pub async fn build_release(
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> std::io::Result<Output> {
+    let mut build_command: Command = Command::new("cargo");
+    build_command
+        .args(["build", "--release"])
+        .current_dir(project_dir);
+    scrub_build_env(&mut build_command);
+    run_with_timeout(build_command, run_config().build_timeout, cancel).await
+}
+
+// Runs the generated project's test suite within the configured build timeout
+pub async fn test_project(
+    project_dir: &Path,
+    cancel: &CancellationToken,
+) -> std::io::Result<Output> {
+    // The tests run AI written code, so they get the server's limits
+    let (test_command, _sandbox) = sandboxed_cargo(&["test"], project_dir)?;
+    run_with_timeout(test_command, run_config().build_timeout, cancel).await
+}
+
+// Name of the binary cargo builds, an explicit [[bin]] wins over the package name
+pub fn binary_name(manifest: &str) -> Option<String> {
+    let manifest: toml::Value = manifest.parse().ok()?;
+    let bin_name: Option<&str> = manifest
+        .get("bin")
+        .and_then(|bins| bins.as_array()?.first()?.get("name")?.as_str());
+    bin_name
+        .or_else(|| manifest.get("package")?.get("name")?.as_str())
+        .map(String::from)
+}
+// The binary cargo build left in the project's target dir
+pub fn server_binary(project_dir: &Path) -> io::Result<PathBuf> {
+    let manifest: String = fs::read_to_string(project_dir.join("Cargo.toml"))?;
+    let bin_name: String = binary_name(&manifest).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "Could not read the binary name from Cargo.toml",
+        )
+    })?;
+    let binary: PathBuf = project_dir.join("target").join("debug").join(format!(
+        "{}{}",
+        bin_name,
+        std::env::consts::EXE_SUFFIX
+    ));
+    if !binary.is_file() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "No server binary at {}, build the project first",
+                binary.display()
+            ),
+        ));
+    }
+    Ok(binary)
+}
+
+// A port nothing listens on right now
+pub fn free_port() -> io::Result<u16> {
+    Ok(TcpListener::bind((SERVER_HOST, 0))?.local_addr()?.port())
+}
+
+// Polls the port until the server accepts connections, failing as soon as the process exits
+pub async fn wait_until_ready(
+    server: &mut ProcessGuard,
+    port: u16,
+    probe: impl Fn() -> bool,
+    timeout: Duration,
+) -> io::Result<()> {
+    let started: Instant = Instant::now();
+    loop {
+        if let Some(status) = server.try_wait()? {
+            return Err(exited_early(status));
+        }
+        if probe() {
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "Server did not accept connections on port {} within {}s",
+                    port,
+                    timeout.as_secs()
+                ),
+            ));
+        }
+        time::sleep(READINESS_POLL_INTERVAL).await;
+    }
+}
+
+fn exited_early(status: ExitStatus) -> Error {
+    Error::other(format!(
+        "Server exited with {} before accepting connections",
+        status
+    ))
+}
+
+// Reduced isolation is reported once per run
+fn report_isolation(sandbox: &Sandbox) {
+    if sandbox.gaps.is_empty() || ISOLATION_REPORTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let gaps_msg: String = format!(
+        "Reduced isolation for the generated server: {}",
+        sandbox.gaps.join("; ")
+    );
+    PrintCommand::Issue.print_agent_message("Sandbox", &gaps_msg);
+}
+
+// Starts the prebuilt server in a sandbox on a free port and waits until it is ready
+pub async fn start_server(project_dir: &Path) -> io::Result<RunningServer> {
+    let binary: PathBuf = server_binary(project_dir)?;
+    let sandbox: Sandbox = prepare_sandbox(project_dir, run_config().isolate_network)?;
+    report_isolation(&sandbox);
+    let port: u16 = free_port()?;
+    let mut run_command: Command = sandboxed_command(&binary, &sandbox)?;
+    run_command
+        .env(HOST_ENV, SERVER_HOST)
+        .env(PORT_ENV, port.to_string());
+    let mut process: ProcessGuard = ProcessGuard::spawn(run_command)?;
+
+    // Behind a loopback-only namespace the server is only reachable from inside it
+    let pid: u32 = process.id();
+    let loopback_only: bool = sandbox.loopback_only;
+    let probe = move || {
+        if loopback_only {
+            connect_in_namespace(pid, port).is_ok()
+        } else {
+            TcpStream::connect((SERVER_HOST, port)).is_ok()
+        }
+    };
+    wait_until_ready(&mut process, port, probe, SERVER_STARTUP_TIMEOUT).await?;
+    let forwarder: Option<NamespaceForwarder> = if loopback_only {
+        Some(NamespaceForwarder::start(pid, port)?)
+    } else {
+        None
+    };
+    let public_port: u16 = forwarder.as_ref().map_or(port, |forwarder| forwarder.port);
+    SERVER_PORT.store(public_port, Ordering::Relaxed);
+    Ok(RunningServer {
+        _process: process,
+        _forwarder: forwarder,
+        _sandbox: sandbox,
+    })
+}
+
+// Base url of the server started last
+pub fn server_base_url() -> String {
+    format!(
+        "http://{}:{}",
+        SERVER_HOST,
+        SERVER_PORT.load(Ordering::Relaxed)
+    )
+}
+
+// Url on the running server, dynamic segments such as {id} are filled with a sample value
+pub fn server_url(route: &str) -> String {
+    server_url_with_id(route, "1")
+}
+
+// Url on the running server with dynamic segments filled with the given id
+pub fn server_url_with_id(route: &str, id: &str) -> String {
+    let path: Vec<&str> = route
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with('{') && segment.ends_with('}') {
+                id
+            } else {
+                segment
+            }
+        })
+        .collect();
+    format!("{}{}", server_base_url(), path.join("/"))
+}
+
+// Sample value matching a JSON schema from infer_schema
+fn sample_value(schema: &Value) -> Value {
+    let schema_type: &Value = match &schema["type"] {
+        Value::Array(types) => types.first().unwrap_or(&Value::Null),
+        schema_type => schema_type,
+    };
+    match schema_type.as_str().unwrap_or_default() {
+        "integer" | "number" => json!(1),
+        "boolean" => json!(true),
+        "string" => json!("sample"),
+        "array" => match schema.get("items") {
+            Some(items) => json!([sample_value(items)]),
+            None => json!([]),
+        },
+        "object" => {
+            let properties: Map<String, Value> = schema["properties"]
+                .as_object()
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, property)| (name.clone(), sample_value(property)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Value::Object(properties)
+        }
+        _ => Value::Null,
+    }
+}
+
+// Payload synthesized from a request body of the endpoint schema, None if the route takes none
+pub fn sample_body(request_body: &Value) -> Option<Value> {
+    (!is_no_body(request_body)).then(|| sample_value(&infer_schema(request_body)))
+}
+
+// Creates run before reads and updates, deletes last, so the sample record exists when needed
+pub fn method_order(method: &str) -> u8 {
+    match method.to_lowercase().as_str() {
+        "post" => 0,
+        "get" => 1,
+        "put" | "patch" => 2,
+        "delete" => 3,
+        _ => 4,
+    }
+}
+
+// Calls a route with any method, returning the status code and body
+pub async fn call_route(
+    client: &Client,
+    method: &str,
+    url: &str,
+    body: Option<&Value>,
+    token: Option<&str>,
+) -> Result<(u16, String), reqwest::Error> {
+    let method: Method =
+        Method::from_bytes(method.to_uppercase().as_bytes()).unwrap_or(Method::GET);
+    let mut request = client.request(method, url);
+    if let Some(body) = body {
+        request = request.json(body);
+    }
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?;
+    let status: u16 = response.status().as_u16();
+    Ok((status, response.text().await.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_server_url_fills_dynamic_segments() {
+        assert!(server_base_url().starts_with("http://127.0.0.1:"));
+        assert_eq!(server_url("/song"), format!("{}/song", server_base_url()));
+        assert_eq!(
+            server_url_with_id("/song/{id}/plays", "4242"),
+            format!("{}/song/4242/plays", server_base_url())
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tests_readiness_probe() {
+        let listener: TcpListener = TcpListener::bind((SERVER_HOST, 0)).unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        let mut command: Command = Command::new("sh");
+        command.args(["-c", "sleep 30"]);
+        let mut running: ProcessGuard = ProcessGuard::spawn(command).unwrap();
+        let probe = || TcpStream::connect((SERVER_HOST, port)).is_ok();
+        wait_until_ready(&mut running, port, probe, Duration::from_secs(5))
+            .await
+            .unwrap();
+        drop(listener);
+
+        let mut command: Command = Command::new("sh");
+        command.args(["-c", "exit 3"]);
+        let mut crashed: ProcessGuard = ProcessGuard::spawn(command).unwrap();
+        let err: Error = wait_until_ready(&mut crashed, port, || false, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("exited"));
+    }
+
+    #[test]
+    fn tests_synthesizes_request_bodies() {
+        assert_eq!(
+            sample_body(&json!({ "id": "number", "title": "string", "done": "bool" })),
+            Some(json!({ "id": 1, "title": "sample", "done": true }))
+        );
+        assert_eq!(
+            sample_body(&json!({ "tags": "Vec<String>", "note": "Option<String>" })),
+            Some(json!({ "tags": ["sample"], "note": "sample" }))
+        );
+        assert_eq!(sample_body(&json!("None")), None);
+        assert!(method_order("post") < method_order("get"));
+        assert!(method_order("PUT") < method_order("delete"));
+    }
+}
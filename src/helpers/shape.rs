@@ -0,0 +1,179 @@
+use crate::helpers::openapi::is_no_body;
+use serde_json::Value;
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_optional(declared: &Value) -> bool {
+    declared
+        .as_str()
+        .is_some_and(|type_name| type_name.trim().starts_with("Option<"))
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn mismatch(path: &str, expected: &str, actual: &Value) -> String {
+    let location: &str = if path.is_empty() { "response" } else { path };
+    format!(
+        "`{}` should be {} but is {}",
+        location,
+        expected,
+        json_type(actual)
+    )
+}
+
+// Checks a value against a type name of the endpoint schema, names it doesn't know pass
+fn type_mismatches(actual: &Value, type_name: &str, path: &str) -> Vec<String> {
+    let type_name: &str = type_name.trim();
+    if let Some(inner) = type_name
+        .strip_prefix("Option<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        return match actual {
+            Value::Null => vec![],
+            _ => type_mismatches(actual, inner, path),
+        };
+    }
+    if let Some(inner) = type_name
+        .strip_prefix("Vec<")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        return match actual {
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .flat_map(|(index, item)| {
+                    type_mismatches(item, inner, &format!("{}[{}]", path, index))
+                })
+                .collect(),
+            _ => vec![mismatch(path, "an array", actual)],
+        };
+    }
+    let (expected, matches): (&str, bool) = match type_name.to_lowercase().as_str() {
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" | "int"
+        | "integer" => ("an integer", actual.is_i64() || actual.is_u64()),
+        "number" | "f32" | "f64" | "float" | "double" => ("a number", actual.is_number()),
+        "bool" | "boolean" => ("a bool", actual.is_boolean()),
+        "string" | "str" | "&str" => ("a string", actual.is_string()),
+        "array" | "list" => ("an array", actual.is_array()),
+        "object" | "map" => ("an object", actual.is_object()),
+        _ => ("", true),
+    };
+    if matches {
+        vec![]
+    } else {
+        vec![mismatch(path, expected, actual)]
+    }
+}
+
+// Structural differences between a response and its declared shape: missing fields, wrong types,
+// object versus array
+pub fn shape_mismatches(actual: &Value, declared: &Value, path: &str) -> Vec<String> {
+    match declared {
+        Value::String(type_name) => type_mismatches(actual, type_name, path),
+        Value::Object(fields) => {
+            let Value::Object(actual_fields) = actual else {
+                return vec![mismatch(path, "an object", actual)];
+            };
+            fields
+                .iter()
+                .flat_map(|(name, field)| match actual_fields.get(name) {
+                    Some(value) => shape_mismatches(value, field, &field_path(path, name)),
+                    None if is_optional(field) => vec![],
+                    None => vec![format!("missing field `{}`", field_path(path, name))],
+                })
+                .collect()
+        }
+        Value::Array(items) => {
+            let Value::Array(actual_items) = actual else {
+                return vec![mismatch(path, "an array", actual)];
+            };
+            match items.first() {
+                Some(item) => actual_items
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, value)| {
+                        shape_mismatches(value, item, &format!("{}[{}]", path, index))
+                    })
+                    .collect(),
+                None => vec![],
+            }
+        }
+        Value::Number(_) if !actual.is_number() => vec![mismatch(path, "a number", actual)],
+        Value::Bool(_) if !actual.is_boolean() => vec![mismatch(path, "a bool", actual)],
+        _ => vec![],
+    }
+}
+
+// Mismatches of a response body against the `response` of its RouteObject
+pub fn response_mismatches(body: &str, declared: &Value) -> Vec<String> {
+    if is_no_body(declared) {
+        return vec![];
+    }
+    match serde_json::from_str::<Value>(body) {
+        Ok(actual) => shape_mismatches(&actual, declared, ""),
+        // A bare string response is fine when a string is declared
+        Err(_)
+            if declared
+                .as_str()
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case("string")) =>
+        {
+            vec![]
+        }
+        Err(_) => vec!["response is not JSON".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tests_finds_shape_mismatches() {
+        let song: Value = json!({
+            "id": "number",
+            "title": "string",
+            "tags": "Vec<String>",
+            "note": "Option<String>"
+        });
+        assert!(
+            response_mismatches(
+                r#"{"id": 1, "title": "Song", "tags": ["pop"], "extra": true}"#,
+                &song
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            response_mismatches(r#"{"id": "1", "tags": [1]}"#, &song),
+            vec![
+                "`id` should be a number but is string",
+                "`tags[0]` should be a string but is number",
+                "missing field `title`",
+            ]
+        );
+        assert_eq!(
+            response_mismatches(r#"[{"id": 1}]"#, &song),
+            vec!["`response` should be an object but is array"]
+        );
+        assert_eq!(
+            response_mismatches("Not found", &json!([song])),
+            vec!["response is not JSON"]
+        );
+        assert!(response_mismatches("", &json!("None")).is_empty());
+    }
+}
@@ -0,0 +1,9 @@
+#[macro_export]
+macro_rules! get_function_string {
+    ($func:ident) => {{ stringify!($func) }};
+}
+#[macro_use]
+pub mod ai_functions;
+pub mod apis;
+pub mod helpers;
+pub mod models;
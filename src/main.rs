@@ -1,24 +1,39 @@
-#[macro_export]
-macro_rules! get_function_string {
-    ($func:ident) => {{ stringify!($func) }};
-}
-#[macro_use]
-mod ai_functions;
-mod apis;
-mod helpers;
-mod models;
-use crate::ai_functions::ai_func_architect::print_project_scope;
-use crate::ai_functions::ai_func_managing::convert_user_input_to_goal; // If it's from ai_func_architect
-use crate::helpers::command_line::get_user_response;
-use crate::helpers::general::*;
-use crate::models::agents_manager::managing_agent::ManagingAgent;
+use backendbro::helpers::command_line::get_user_response;
+use backendbro::helpers::config::{Cli, init_run_config};
+use backendbro::helpers::outcome::exit_code;
+use backendbro::helpers::process::cancel_on_ctrl_c;
+use backendbro::models::agents_manager::checkpoint::Checkpoint;
+use backendbro::models::agents_manager::managing_agent::ManagingAgent;
+use clap::Parser;
+use std::process::ExitCode;
+use tokio_util::sync::CancellationToken;
 #[tokio::main]
-async fn main() {
-    let user_req = get_user_response("What WebServer are we building today !?");
+async fn main() -> ExitCode {
+    let cli: Cli = Cli::parse();
+    init_run_config(cli.run_config());
+    // The project directory and its checkpoint stay on disk whatever the outcome
+    match run(&cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(exit_code(err.as_ref()))
+        }
+    }
+}
 
-    let mut manage_agent: ManagingAgent = ManagingAgent::new(user_req)
-        .await
-        .expect("Error creating agent");
+async fn run(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut manage_agent: ManagingAgent = match &cli.resume {
+        Some(checkpoint_path) => ManagingAgent::from_checkpoint(Checkpoint::load(checkpoint_path)?),
+        None => {
+            let user_req: String = match cli.user_request()? {
+                Some(user_req) => user_req,
+                None => get_user_response("What WebServer are we building today !?"),
+            };
+            ManagingAgent::new(user_req).await?
+        }
+    };
 
-    manage_agent.execute_project().await;
+    let cancel: CancellationToken = CancellationToken::new();
+    cancel_on_ctrl_c(cancel.clone());
+    manage_agent.execute_project(&cancel).await
 }
@@ -1,6 +1,7 @@
 use crate::ai_functions::ai_func_architect::{print_project_scope, print_site_urls};
 use crate::helpers::command_line::PrintCommand;
 use crate::helpers::general::{ai_task_request_decoded, check_status_code};
+use crate::helpers::process::check_cancelled;
 use crate::models::agents::agents_traits::{FactSheet, ProjectScope, SpecialFunctions};
 use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
 use crate::models::agents_basic::basic_trait::BasicTraits;
@@ -9,6 +10,7 @@ use crate::models::agents_basic::basic_trait::BasicTraits;
 use async_trait::async_trait;
 use reqwest::Client;
 use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 // Solutions Architect
 #[derive(Debug)]
@@ -30,7 +32,10 @@ impl AgentSolutionArchitect {
     }
 
     // Retrieve Project Scope
-    async fn call_project_scope(&mut self, factsheet: &mut FactSheet) -> ProjectScope {
+    async fn call_project_scope(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<ProjectScope, Box<dyn std::error::Error>> {
         let msg_context: String = format!("{}", factsheet.project_description);
 
         let project_scope: ProjectScope = ai_task_request_decoded::<ProjectScope>(
@@ -39,11 +44,11 @@ impl AgentSolutionArchitect {
             get_function_string!(print_project_scope),
             print_project_scope,
         )
-        .await;
+        .await?;
 
         factsheet.project_scope = Some(project_scope.clone());
         self.attributes.update_state(AgentState::Finished);
-        project_scope
+        Ok(project_scope)
     }
 
     // Retrieve Project Scope
@@ -51,17 +56,23 @@ impl AgentSolutionArchitect {
         &mut self,
         factsheet: &mut FactSheet,
         msg_context: String,
-    ) {
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let ai_response: Vec<String> = ai_task_request_decoded::<Vec<String>>(
             msg_context,
             &self.attributes.position,
             get_function_string!(print_site_urls),
             print_site_urls,
         )
-        .await;
+        .await?;
 
         factsheet.external_urls = Some(ai_response);
         self.attributes.state = AgentState::UnitTesting;
+        Ok(())
+    }
+}
+impl Default for AgentSolutionArchitect {
+    fn default() -> Self {
+        Self::new()
     }
 }
 #[async_trait]
@@ -73,12 +84,14 @@ impl SpecialFunctions for AgentSolutionArchitect {
     async fn execute(
         &mut self,
         factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // !!! WARNING - BE CAREFUL OF INFINITATE LOOPS !!!
         while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
             match self.attributes.state {
                 AgentState::Discovery => {
-                    let project_scope: ProjectScope = self.call_project_scope(factsheet).await;
+                    let project_scope: ProjectScope = self.call_project_scope(factsheet).await?;
 
                     // Confirm if external urls
                     if project_scope.is_external_urls_required {
@@ -86,7 +99,7 @@ impl SpecialFunctions for AgentSolutionArchitect {
                             factsheet,
                             factsheet.project_description.clone(),
                         )
-                        .await;
+                        .await?;
                         self.attributes.state = AgentState::UnitTesting;
                     }
                 }
@@ -107,6 +120,7 @@ impl SpecialFunctions for AgentSolutionArchitect {
 
                     // Find faulty urls
                     for url in urls {
+                        check_cancelled(cancel)?;
                         let endpoint_str: String = format!("Testing URL Endpoint: {}", url);
                         PrintCommand::UnitTest.print_agent_message(
                             self.attributes.position.as_str(),
@@ -164,12 +178,11 @@ mod tests {
             project_description: "Build a full stack website that shows me sports news".to_string(),
             project_scope: None,
             external_urls: None,
-            backend_code: None,
-            api_endpoint_schema: None,
+            ..Default::default()
         };
 
         agent
-            .execute(&mut factsheet)
+            .execute(&mut factsheet, &CancellationToken::new())
             .await
             .expect("Unable to execute Solutions Architect Agent");
         assert!(factsheet.project_scope != None);
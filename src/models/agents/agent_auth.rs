@@ -0,0 +1,484 @@
+use crate::ai_functions::ai_func_auth::print_auth_webserver_code;
+use crate::ai_functions::ai_func_backend::{print_fixed_code, print_fixed_code_edits};
+use crate::helpers::command_line::{PrintCommand, confirm_safe_code};
+use crate::helpers::config::run_config;
+use crate::helpers::diagnostics::build_errors;
+use crate::helpers::general::{
+    EXEC_MAIN_PATH, add_project_dependency, ai_task_request, read_exec_main_contents,
+    save_api_endpoints, save_backend_code,
+};
+use crate::helpers::openapi::save_openapi_document;
+use crate::helpers::outcome::{self, BudgetExhausted};
+use crate::helpers::patch::patch_backend_code;
+use crate::helpers::policy::{PolicyFinding, lint_project};
+use crate::helpers::process::check_cancelled;
+use crate::helpers::server::{
+    FixedBuild, RunningServer, build_applying_suggestions, build_project, call_route, server_url,
+    start_server,
+};
+use crate::models::agents::agent_backend::extract_rest_api_endpoints;
+use crate::models::agents::agents_traits::{
+    FactSheet, FixAttempt, FixReport, RouteObject, SpecialFunctions,
+};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// Routes anonymous users must still be able to call
+pub const PUBLIC_ROUTES: [&str; 2] = ["/register", "/login"];
+const ARGON2_DEPENDENCY: &str = "argon2 = \"0.5\"";
+const PROBE_USERNAME: &str = "backendbro_probe";
+const PROBE_PASSWORD: &str = "probe-Password-123";
+// What later fixes of the backend must keep, for their prompts
+pub const AUTH_REQUIREMENTS: &str = "POST /register, POST /login and POST /logout stay as they are. \
+The App::wrap_fn middleware that answers 401 Unauthorized to requests without a valid \
+\"Authorization: Bearer <token>\" header stays on every route except /register, /login and /app. \
+Passwords stay hashed with argon2";
+
+// Authentication Engineer
+#[derive(Debug)]
+pub struct AgentAuthEngineer {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+    // Backend code and schema from before auth was added, restored if auth can not be made to work
+    original_code: Option<String>,
+    original_schema: Option<Vec<RouteObject>>,
+    attempts: Vec<FixAttempt>,
+}
+
+impl AgentAuthEngineer {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Adds register, login and logout with hashed passwords to the backend"
+                .to_string(),
+            position: "Authentication Engineer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+            original_code: None,
+            original_schema: None,
+            attempts: vec![],
+        }
+    }
+
+    async fn call_auth_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let backend_code: String = read_exec_main_contents(factsheet.project_path());
+        let msg_context: String = format!(
+            "BACKEND_CODE: {} \n PROJECT_DESCRIPTION: {}",
+            backend_code, factsheet.project_description
+        );
+        self.original_code = Some(backend_code);
+        self.original_schema = factsheet.api_endpoint_schema.clone();
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_auth_webserver_code),
+            print_auth_webserver_code,
+        )
+        .await?;
+        save_backend_code(factsheet.project_path(), &ai_response);
+        factsheet.backend_code = Some(ai_response);
+        Ok(())
+    }
+
+    async fn call_fix_auth_bugs(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            " BROKEN CODE :\n{}\n  ERROR_BUGS:\n{} \n",
+            read_exec_main_contents(factsheet.project_path()),
+            self.bug_errors.as_deref().unwrap_or_default()
+        );
+        let code: String = patch_backend_code(
+            factsheet.project_path(),
+            msg_context,
+            &self.attributes.position,
+            (
+                get_function_string!(print_fixed_code_edits),
+                print_fixed_code_edits,
+            ),
+            (get_function_string!(print_fixed_code), print_fixed_code),
+        )
+        .await?;
+        factsheet.backend_code = Some(code);
+        Ok(())
+    }
+
+    // Hands back the working backend rather than a broken auth layer, rebuilt so the binary in
+    // target/ matches it again
+    async fn restore_backend(
+        &self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(original_code) = &self.original_code else {
+            return Ok(());
+        };
+        fs::write(factsheet.project_path().join(EXEC_MAIN_PATH), original_code)?;
+        factsheet.backend_code = Some(original_code.clone());
+        if let Some(routes) = &self.original_schema {
+            save_api_endpoints(factsheet.project_path(), &serde_json::to_string(routes)?);
+            save_openapi_document(
+                factsheet.project_path(),
+                &factsheet.project_description,
+                routes,
+            );
+            factsheet.api_endpoint_schema = Some(routes.clone());
+        }
+        let restore_msg: &str = if build_project(factsheet.project_path(), cancel)
+            .await?
+            .status
+            .success()
+        {
+            "Auth Unit Testing: Too many bugs, restored and rebuilt the backend without auth"
+        } else {
+            "Auth Unit Testing: Too many bugs, restored the backend without auth but it no longer builds"
+        };
+        PrintCommand::Issue.print_agent_message(self.attributes.position.as_str(), restore_msg);
+        Ok(())
+    }
+
+    // Out of fix attempts: reports what is left of the auth layer
+    fn give_up(&self, factsheet: &mut FactSheet) -> std::io::Result<BudgetExhausted> {
+        outcome::give_up(
+            factsheet,
+            FixReport {
+                agent: self.attributes.position.clone(),
+                bug_budget: run_config().bug_budget,
+                last_compiling_revision: None,
+                remaining_errors: self.bug_errors.clone().unwrap_or_default(),
+                attempts: self.attempts.clone(),
+            },
+            self.original_code.as_deref(),
+        )
+    }
+}
+
+impl Default for AgentAuthEngineer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Whether auth was added to the backend, later agents have to keep it working
+pub fn has_auth(factsheet: &FactSheet) -> bool {
+    let is_login_required: bool = factsheet
+        .project_scope
+        .as_ref()
+        .is_some_and(|scope| scope.is_user_login_and_logout);
+    is_login_required
+        && factsheet
+            .api_endpoint_schema
+            .as_ref()
+            .is_some_and(|routes| routes.iter().any(|route| route.route == "/login"))
+}
+
+// Anonymous calls must be refused and a logged in user let through until logout
+pub async fn run_auth_checks(routes: &[RouteObject]) -> Vec<String> {
+    let client: Client = Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+    let mut failures: Vec<String> = vec![];
+
+    for route_object in protected_routes(routes) {
+        let url: String = server_url(&route_object.route);
+        let body: Option<serde_json::Value> =
+            matches!(route_object.method.as_str(), "post" | "put" | "patch")
+                .then(|| serde_json::json!({}));
+        match call_route(&client, &route_object.method, &url, body.as_ref(), None).await {
+            Ok((401, _)) => {}
+            Ok((status, _)) => failures.push(format!(
+                "{} {} answered {} to an anonymous call instead of 401",
+                route_object.method.to_uppercase(),
+                route_object.route,
+                status
+            )),
+            Err(e) => failures.push(format!(
+                "{} {} failed: {}",
+                route_object.method.to_uppercase(),
+                route_object.route,
+                e
+            )),
+        }
+    }
+
+    let credentials: serde_json::Value = serde_json::json!({
+        "username": PROBE_USERNAME,
+        "password": PROBE_PASSWORD,
+    });
+    let _ = call_route(
+        &client,
+        "post",
+        &server_url("/register"),
+        Some(&credentials),
+        None,
+    )
+    .await;
+    let token: Option<String> = match call_route(
+        &client,
+        "post",
+        &server_url("/login"),
+        Some(&credentials),
+        None,
+    )
+    .await
+    {
+        Ok((200, body)) => extract_token(&body),
+        _ => None,
+    };
+    let Some(token) = token else {
+        failures.push("POST /login did not return a token after POST /register".to_string());
+        return failures;
+    };
+
+    // A protected GET route shows the token opens the door and logout closes it again
+    let probe: Option<&RouteObject> = protected_routes(routes)
+        .into_iter()
+        .find(|route_object| route_object.method == "get" && route_object.route != "/logout");
+    if let Some(probe) = probe {
+        let url: String = server_url(&probe.route);
+        if let Ok((401, _)) = call_route(&client, "get", &url, None, Some(&token)).await {
+            failures.push(format!("GET {} rejected a logged in user", probe.route));
+        }
+        let _ = call_route(&client, "post", &server_url("/logout"), None, Some(&token)).await;
+        if !matches!(
+            call_route(&client, "get", &url, None, Some(&token)).await,
+            Ok((401, _))
+        ) {
+            failures.push(format!(
+                "GET {} still accepts a token after logout",
+                probe.route
+            ));
+        }
+    }
+    failures
+}
+
+// Every route except the public register and login ones
+pub fn protected_routes(routes: &[RouteObject]) -> Vec<&RouteObject> {
+    routes
+        .iter()
+        .filter(|route_object| {
+            !PUBLIC_ROUTES.contains(&route_object.route.as_str())
+                && !route_object.route.starts_with("/app")
+        })
+        .collect()
+}
+
+// Token returned by POST /login
+pub fn extract_token(login_body: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(login_body)
+        .ok()?
+        .get("token")?
+        .as_str()
+        .map(String::from)
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentAuthEngineer {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let is_login_required: bool = factsheet
+            .project_scope
+            .as_ref()
+            .is_some_and(|scope| scope.is_user_login_and_logout);
+        if !is_login_required {
+            PrintCommand::AICall.print_agent_message(
+                self.attributes.position.as_str(),
+                "No login and logout required, skipping authentication",
+            );
+            self.attributes.state = AgentState::Finished;
+        }
+
+        while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    let manifest_path: PathBuf = factsheet.project_path().join("Cargo.toml");
+                    let manifest: String = fs::read_to_string(&manifest_path)?;
+                    if let Some(manifest) = add_project_dependency(&manifest, ARGON2_DEPENDENCY) {
+                        fs::write(&manifest_path, manifest)?;
+                    }
+                    self.call_auth_backend_code(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    self.call_fix_auth_bugs(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    PrintCommand::UnitTest.print_agent_message(
+                        self.attributes.position.as_str(),
+                        "Auth Unit Testing : Requesting User Input",
+                    );
+                    let findings: Vec<PolicyFinding> = lint_project(
+                        factsheet.project_path(),
+                        factsheet.external_urls.as_deref().unwrap_or_default(),
+                    );
+                    if !confirm_safe_code(&findings) {
+                        return Err("AI written auth code was not approved".into());
+                    }
+
+                    let build: FixedBuild =
+                        build_applying_suggestions(factsheet.project_path(), cancel).await?;
+                    factsheet.record_diagnostics(&self.attributes.position, &build.diagnostics);
+                    if build.applied > 0 {
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            &format!(
+                                "Auth Unit Testing: applied {} compiler suggestions",
+                                build.applied
+                            ),
+                        );
+                        factsheet.backend_code =
+                            Some(read_exec_main_contents(factsheet.project_path()));
+                    }
+                    let stage: &str = if build.output.status.success() {
+                        "auth checks"
+                    } else {
+                        "build"
+                    };
+                    let failures: Vec<String> = if build.output.status.success() {
+                        let api_endpoints_str: String = extract_rest_api_endpoints(
+                            &self.attributes.position,
+                            factsheet.project_path(),
+                        )
+                        .await?;
+                        let api_endpoints: Vec<RouteObject> =
+                            serde_json::from_str(api_endpoints_str.as_str())?;
+                        save_api_endpoints(factsheet.project_path(), &api_endpoints_str);
+                        save_openapi_document(
+                            factsheet.project_path(),
+                            &factsheet.project_description,
+                            &api_endpoints,
+                        );
+                        factsheet.api_endpoint_schema = Some(api_endpoints.clone());
+
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Auth Unit Testing: Calling protected routes anonymously...",
+                        );
+                        let server: RunningServer = start_server(factsheet.project_path()).await?;
+                        let failures: Vec<String> = run_auth_checks(&api_endpoints).await;
+                        drop(server);
+                        failures
+                    } else {
+                        vec![build_errors(
+                            &build.diagnostics,
+                            &build.output.stderr,
+                            factsheet.project_path(),
+                        )]
+                    };
+
+                    if failures.is_empty() {
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Auth Unit Testing: protected routes reject anonymous calls",
+                        );
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+
+                    for failure in &failures {
+                        PrintCommand::Issue
+                            .print_agent_message(self.attributes.position.as_str(), failure);
+                    }
+                    self.bug_errors = Some(failures.join("\n"));
+                    self.attempts.push(FixAttempt {
+                        attempt: self.attempts.len() + 1,
+                        stage: stage.to_string(),
+                        errors: failures.len(),
+                        warnings: 0,
+                        diagnostics: failures.join("\n"),
+                    });
+                    self.bug_count += 1;
+                    if self.bug_count > run_config().bug_budget {
+                        self.restore_backend(factsheet, cancel).await?;
+                        return Err(Box::new(self.give_up(factsheet)?));
+                    }
+                    self.attributes.state = AgentState::Working;
+                }
+                _ => {
+                    self.attributes.state = AgentState::Finished;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agents::agents_traits::ProjectScope;
+
+    #[test]
+    fn tests_protected_routes_skip_public_ones() {
+        let routes: Vec<RouteObject> = serde_json::from_str(
+            r#"[
+              {"route": "/register", "is_route_dynamic": "false", "method": "post", "request_body": "None", "response": "None"},
+              {"route": "/login", "is_route_dynamic": "false", "method": "post", "request_body": "None", "response": "None"},
+              {"route": "/logout", "is_route_dynamic": "false", "method": "post", "request_body": "None", "response": "None"},
+              {"route": "/task/{id}", "is_route_dynamic": "true", "method": "get", "request_body": "None", "response": "None"}
+            ]"#,
+        )
+        .unwrap();
+        let protected: Vec<&str> = protected_routes(&routes)
+            .iter()
+            .map(|route_object| route_object.route.as_str())
+            .collect();
+        assert_eq!(protected, vec!["/logout", "/task/{id}"]);
+    }
+
+    #[test]
+    fn tests_extracts_login_token() {
+        assert_eq!(
+            extract_token(r#"{"token": "5f1c"}"#).as_deref(),
+            Some("5f1c")
+        );
+        assert_eq!(extract_token("Logged in!"), None);
+    }
+
+    #[test]
+    fn tests_has_auth_needs_scope_and_login_route() {
+        let login: Vec<RouteObject> = serde_json::from_str(
+            r#"[{"route": "/login", "is_route_dynamic": "false", "method": "post", "request_body": "None", "response": "None"}]"#,
+        )
+        .unwrap();
+        let mut factsheet: FactSheet = FactSheet {
+            project_scope: Some(ProjectScope {
+                is_crud_required: true,
+                is_user_login_and_logout: true,
+                is_external_urls_required: false,
+            }),
+            api_endpoint_schema: Some(vec![]),
+            ..Default::default()
+        };
+        // Auth was asked for but not added yet
+        assert!(!has_auth(&factsheet));
+        factsheet.api_endpoint_schema = Some(login);
+        assert!(has_auth(&factsheet));
+    }
+}
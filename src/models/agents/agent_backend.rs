@@ -1,34 +1,63 @@
-use std::{process::Command, thread, time::Duration};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
 use crate::ai_functions::ai_func_backend::{
-    print_backend_webserver_code, print_fixed_code, print_improved_webserver_code,
+    print_backend_webserver_code, print_fixed_code, print_fixed_code_edits,
+    print_improved_webserver_code, print_improved_webserver_edits, print_optimized_webserver_code,
     print_rest_api_endpoints,
 };
+use crate::helpers::config::run_config;
+use crate::helpers::diagnostics::{build_errors, count_diagnostics};
+use crate::helpers::gates::{Gate, GateOutcome, run_gate};
 use crate::helpers::general::extract_code_block;
 use crate::helpers::general::{
-    WEB_TEMPLATE_PATH, check_status_code, read_code_template_contents, read_exec_main_contents,
-    save_api_endpoints,
+    EXEC_MAIN_PATH, read_code_template_contents, read_exec_main_contents, save_api_endpoints,
+    save_backend_code,
 };
-use crate::{ai_task_request_decoded, save_backend_code};
 
 use crate::helpers::command_line::{PrintCommand, confirm_safe_code};
 use crate::helpers::general::ai_task_request;
-use crate::models::agents::agents_traits::{FactSheet, RouteObject, SpecialFunctions};
+use crate::helpers::lifecycle::{plan_lifecycles, run_lifecycle};
+use crate::helpers::load_test::{LOAD_TEST_CONCURRENCY, LOAD_TEST_REQUESTS, run_load_test};
+use crate::helpers::openapi::save_openapi_document;
+use crate::helpers::outcome::{self, BudgetExhausted};
+use crate::helpers::patch::{patch_backend_code, regenerated_code};
+use crate::helpers::policy::{PolicyFinding, lint_project};
+use crate::models::agents::agent_reviewer::AgentCodeReviewer;
+use crate::models::agents::agents_traits::{
+    CodeReview, FactSheet, FixAttempt, FixReport, GateResult, LifecycleStep, LoadReport,
+    RouteCheck, RouteObject, SpecialFunctions,
+};
 use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
 
+use crate::helpers::process::check_cancelled;
+use crate::helpers::server::{
+    FixedBuild, RunningServer, build_applying_suggestions, build_project, call_route, method_order,
+    sample_body, server_url, start_server,
+};
+use crate::helpers::shape::response_mismatches;
+use crate::models::agents::agent_database::{INITIAL_MIGRATION, MIGRATIONS_DIR};
 use async_trait::async_trait;
-use crossterm::cursor::position;
-use crossterm::style::Print;
 use reqwest::Client;
-use std::process::Stdio;
-use tokio::time;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+// Review and improve rounds before the code goes to testing regardless
+const MAX_REVIEW_ROUNDS: u8 = 2;
 
-use super::agents_traits::ProjectScope;
 #[derive(Debug)]
 pub struct AgentBackendDeveloper {
     attributes: BasicAgent,
     bug_errors: Option<String>,
     bug_count: u8,
+    review_rounds: u8,
+    // Shape fixes share the bug budget, counted apart as a passing build resets bug_count
+    response_fix_rounds: u8,
+    // Gate whose failure is being fixed, None for build errors
+    failed_gate: Option<Gate>,
+    attempts: Vec<FixAttempt>,
+    last_compiling_code: Option<String>,
 }
 impl AgentBackendDeveloper {
     pub fn new() -> Self {
@@ -43,14 +72,37 @@ impl AgentBackendDeveloper {
             attributes,
             bug_errors: None,
             bug_count: 0,
+            review_rounds: 0,
+            response_fix_rounds: 0,
+            failed_gate: None,
+            attempts: vec![],
+            last_compiling_code: None,
+        }
+    }
+    // Schema designed by the Database Designer, if there is one
+    fn database_context(factsheet: &FactSheet) -> String {
+        match &factsheet.data_model {
+            Some(data_model) => format!(
+                "DATA_MODEL: {} \n  SQL_MIGRATIONS ({}/{}): {} \n",
+                serde_json::to_string(&data_model.entities).unwrap(),
+                MIGRATIONS_DIR,
+                INITIAL_MIGRATION,
+                data_model.migrations.join("\n")
+            ),
+            None => String::new(),
         }
     }
-    async fn call_initial_backend_code(&mut self, factsheet: &mut FactSheet) {
+    async fn call_initial_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let code_template_str: String = read_code_template_contents();
         //Concatenate Instructions
-        let mut msg_context: String = format!(
-            "CODE TEMPLATE : {} \n  PROJECT_DESCRIPTION:{} \n",
-            code_template_str, factsheet.project_description
+        let msg_context: String = format!(
+            "CODE TEMPLATE : {} \n  PROJECT_DESCRIPTION:{} \n{}",
+            code_template_str,
+            factsheet.project_description,
+            Self::database_context(factsheet)
         );
         let ai_response = ai_task_request(
             msg_context,
@@ -58,64 +110,451 @@ impl AgentBackendDeveloper {
             get_function_string!(print_backend_webserver_code),
             print_backend_webserver_code,
         )
-        .await;
+        .await?;
         // dbg!(&ai_response);
-        save_backend_code(&ai_response);
+        save_backend_code(factsheet.project_path(), &ai_response);
         factsheet.backend_code = Some(ai_response);
+        Ok(())
     }
-    async fn call_improved_backend_code(&mut self, factsheet: &mut FactSheet) {
-        let code_template_str: String = read_code_template_contents();
+    // Edits of the code in place, the whole file is only rewritten when they don't apply
+    async fn call_improved_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         //Concatenate Instructions
-        let mut msg_context: String = format!(
-            "CODE TEMPLATE : {:?} \n  PROJECT_DESCRIPTION:{:?} \n",
-            code_template_str, factsheet.project_description
+        let msg_context: String = format!(
+            "PROJECT_DESCRIPTION:{:?} \n  BACKEND_CODE :\n{}\n  CODE_REVIEW: {} \n{}",
+            factsheet.project_description,
+            read_exec_main_contents(factsheet.project_path()),
+            serde_json::to_string(&factsheet.code_review).unwrap(),
+            Self::database_context(factsheet)
         );
-        let ai_response = ai_task_request(
+        let code: String = patch_backend_code(
+            factsheet.project_path(),
             msg_context,
             &self.attributes.position,
-            get_function_string!(print_backend_webserver_code),
-            print_backend_webserver_code,
+            (
+                get_function_string!(print_improved_webserver_edits),
+                print_improved_webserver_edits,
+            ),
+            (
+                get_function_string!(print_improved_webserver_code),
+                print_improved_webserver_code,
+            ),
         )
-        .await;
-        save_backend_code(&ai_response);
-        factsheet.backend_code = Some(ai_response);
+        .await?;
+        factsheet.backend_code = Some(code);
+        Ok(())
     }
-    async fn call_fix_code_bugs(&mut self, factsheet: &mut FactSheet) {
-        let code_template_str: String = read_code_template_contents();
+    async fn call_fix_code_bugs(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         //Concatenate Instructions
-        let mut msg_context: String = format!(
-            " BROKEN CODE :{:?} \n  PROJECT_DESCRIPTION:{:?} \n
-                THIS FUNCTION ONLY OUTCPUTS CODE. JUST OUTPUT THE CODE",
-            factsheet.backend_code, self.bug_errors
+        let gate: String = self
+            .failed_gate
+            .map(|gate| format!("  GATE {}: {} \n", gate.name(), gate.fix_instruction()))
+            .unwrap_or_default();
+        let msg_context: String = format!(
+            " BROKEN CODE :\n{}\n{}  ERROR_BUGS:\n{} \n",
+            read_exec_main_contents(factsheet.project_path()),
+            gate,
+            self.bug_errors.as_deref().unwrap_or_default()
         );
-        let ai_response = ai_task_request(
+        let code: String = patch_backend_code(
+            factsheet.project_path(),
             msg_context,
             &self.attributes.position,
-            get_function_string!(print_fixed_code),
-            print_fixed_code,
+            (
+                get_function_string!(print_fixed_code_edits),
+                print_fixed_code_edits,
+            ),
+            (get_function_string!(print_fixed_code), print_fixed_code),
         )
-        .await;
-        save_backend_code(&ai_response);
-        factsheet.backend_code = Some(ai_response);
+        .await?;
+        factsheet.backend_code = Some(code);
+        Ok(())
     }
-    async fn call_extract_rest_api_endpoints(&self) -> String {
-        let backend_code = read_exec_main_contents();
-        // Structuring msg_context
-        let msg_context = format!("CODE_INPUT: {}", backend_code);
+    // Writes the optimized main.rs, returns false when the response is no usable code
+    async fn call_optimized_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+        report: &LoadReport,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "BACKEND_CODE: {} \n LOAD_REPORT: {}",
+            read_exec_main_contents(factsheet.project_path()),
+            serde_json::to_string(report).unwrap()
+        );
         let ai_response = ai_task_request(
             msg_context,
             &self.attributes.position,
-            get_function_string!(print_rest_api_endpoints),
-            print_rest_api_endpoints,
+            get_function_string!(print_optimized_webserver_code),
+            print_optimized_webserver_code,
+        )
+        .await?;
+        match regenerated_code(&ai_response) {
+            Ok(code) => {
+                fs::write(factsheet.project_path().join(EXEC_MAIN_PATH), code)?;
+                factsheet.backend_code = Some(ai_response);
+                Ok(true)
+            }
+            Err(problem) => {
+                PrintCommand::Issue.print_agent_message(
+                    self.attributes.position.as_str(),
+                    &format!("Optimized main.rs is unusable ({})", problem),
+                );
+                Ok(false)
+            }
+        }
+    }
+    // Out of fix attempts: keeps everything on disk and reports what is left
+    fn give_up(&self, factsheet: &mut FactSheet) -> std::io::Result<BudgetExhausted> {
+        outcome::give_up(
+            factsheet,
+            FixReport {
+                agent: self.attributes.position.clone(),
+                bug_budget: run_config().bug_budget,
+                last_compiling_revision: None,
+                remaining_errors: self.bug_errors.clone().unwrap_or_default(),
+                attempts: self.attempts.clone(),
+            },
+            self.last_compiling_code.as_deref(),
+        )
+    }
+    // Runs the configured gates in order and returns the first one that fails
+    async fn run_gates(
+        &self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<Option<GateOutcome>, Box<dyn std::error::Error>> {
+        for gate in &run_config().gates {
+            check_cancelled(cancel)?;
+            PrintCommand::UnitTest.print_agent_message(
+                self.attributes.position.as_str(),
+                &format!("Backend Code Unit Testing: {} gate", gate.name()),
+            );
+            let outcome: GateOutcome = run_gate(*gate, factsheet.project_path(), cancel).await?;
+            factsheet.gate_results.push(GateResult {
+                agent: self.attributes.position.clone(),
+                gate: gate.name().to_string(),
+                passed: outcome.passed,
+                errors: outcome.errors,
+                warnings: outcome.warnings,
+            });
+            if *gate == Gate::Fmt {
+                factsheet.backend_code = Some(read_exec_main_contents(factsheet.project_path()));
+            }
+            if !outcome.passed {
+                PrintCommand::Issue.print_agent_message(
+                    self.attributes.position.as_str(),
+                    &format!("Backend Code Unit Testing: {} gate failed", gate.name()),
+                );
+                return Ok(Some(outcome));
+            }
+        }
+        Ok(None)
+    }
+    // Calls every route, creates first and deletes last, recording the status or the error
+    async fn check_routes(
+        &self,
+        routes: &[RouteObject],
+        cancel: &CancellationToken,
+    ) -> Result<Vec<RouteCheck>, Box<dyn std::error::Error>> {
+        let client: Client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let mut ordered_routes: Vec<&RouteObject> = routes.iter().collect();
+        ordered_routes.sort_by_key(|route_object| method_order(&route_object.method));
+
+        let mut checks: Vec<RouteCheck> = vec![];
+        for route_object in ordered_routes {
+            check_cancelled(cancel)?;
+            let method: String = route_object.method.to_lowercase();
+            let testing_msg: String = format!(
+                "Testing endpoint {} '{}'...",
+                method.to_uppercase(),
+                route_object.route
+            );
+            PrintCommand::UnitTest
+                .print_agent_message(self.attributes.position.as_str(), testing_msg.as_str());
+
+            let url: String = server_url(&route_object.route);
+            let body: Option<Value> = sample_body(&route_object.request_body);
+            let check: RouteCheck =
+                match call_route(&client, &method, &url, body.as_ref(), None).await {
+                    Ok((status, response_body)) => RouteCheck {
+                        // Only a successful answer is expected to have the declared shape
+                        mismatches: if (200..300).contains(&status) {
+                            response_mismatches(&response_body, &route_object.response)
+                        } else {
+                            vec![]
+                        },
+                        method,
+                        route: route_object.route.clone(),
+                        status: Some(status),
+                        error: None,
+                    },
+                    Err(e) => RouteCheck {
+                        method,
+                        route: route_object.route.clone(),
+                        status: None,
+                        error: Some(e.to_string()),
+                        mismatches: vec![],
+                    },
+                };
+            if check.passed() {
+                println!(
+                    "The backend url : {} {} works",
+                    check.method.to_uppercase(),
+                    check.route
+                );
+            } else {
+                let err_msg: String = match (&check.status, &check.error) {
+                    (Some(status), _) => format!(
+                        "WARNING: {} {} answered {}",
+                        check.method.to_uppercase(),
+                        check.route,
+                        status
+                    ),
+                    (None, error) => format!(
+                        "WARNING: Failed to call {} {}: {}",
+                        check.method.to_uppercase(),
+                        check.route,
+                        error.as_deref().unwrap_or_default()
+                    ),
+                };
+                PrintCommand::Issue
+                    .print_agent_message(self.attributes.position.as_str(), err_msg.as_str());
+            }
+            checks.push(check);
+        }
+        Ok(checks)
+    }
+    // Runs create, read, update and delete on each resource with the id the server handed out
+    async fn check_lifecycles(
+        &self,
+        routes: &[RouteObject],
+        cancel: &CancellationToken,
+    ) -> Result<Vec<LifecycleStep>, Box<dyn std::error::Error>> {
+        let client: Client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let mut steps: Vec<LifecycleStep> = vec![];
+        for lifecycle in plan_lifecycles(routes) {
+            check_cancelled(cancel)?;
+            for step in run_lifecycle(&client, &lifecycle).await {
+                let step_msg: String = format!(
+                    "Lifecycle {} {}: {} {} -> {}",
+                    step.resource,
+                    step.step,
+                    step.method.to_uppercase(),
+                    step.route,
+                    step.status
+                        .map(|status| status.to_string())
+                        .or(step.error.clone())
+                        .unwrap_or_default()
+                );
+                if step.passed {
+                    println!("{}", step_msg);
+                } else {
+                    PrintCommand::Issue
+                        .print_agent_message(self.attributes.position.as_str(), &step_msg);
+                }
+                steps.push(step);
+            }
+        }
+        Ok(steps)
+    }
+    fn print_load_report(&self, report: &LoadReport) {
+        let report_msg: String = format!(
+            "Load test ({}): {} requests, {} errors, {:.0} req/s, p50 {:.1}ms, p99 {:.1}ms",
+            report.label,
+            report.requests,
+            report.errors,
+            report.throughput_rps,
+            report.p50_ms,
+            report.p99_ms
+        );
+        PrintCommand::UnitTest.print_agent_message(self.attributes.position.as_str(), &report_msg);
+    }
+    // Measures the GET routes, over the p99 threshold the model gets one optimization pass
+    // which is only kept if it passes the gates and route checks again and measures faster
+    async fn run_load_tests(
+        &mut self,
+        factsheet: &mut FactSheet,
+        routes: &[RouteObject],
+        urls: &[String],
+        server: RunningServer,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let client: Client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let baseline: LoadReport = run_load_test(
+            &client,
+            "baseline",
+            urls,
+            LOAD_TEST_REQUESTS,
+            LOAD_TEST_CONCURRENCY,
         )
         .await;
+        drop(server);
+        self.print_load_report(&baseline);
+        factsheet.load_reports.push(baseline.clone());
 
-        extract_code_block(ai_response)
-            .expect("Can't extract code")
-            .trim()
-            .to_string()
+        let threshold_ms: f64 = run_config().p99_threshold.as_secs_f64() * 1000.0;
+        if baseline.p99_ms <= threshold_ms {
+            return Ok(());
+        }
+        check_cancelled(cancel)?;
+        let threshold_msg: String = format!(
+            "p99 latency {:.1}ms is over {:.0}ms, asking for an optimization",
+            baseline.p99_ms, threshold_ms
+        );
+        PrintCommand::Issue.print_agent_message(self.attributes.position.as_str(), &threshold_msg);
+
+        let original_code: String = read_exec_main_contents(factsheet.project_path());
+        let original_response: Option<String> = factsheet.backend_code.clone();
+        let mut optimized: Option<LoadReport> = None;
+        if self
+            .call_optimized_backend_code(factsheet, &baseline)
+            .await?
+            && confirm_safe_code(&lint_project(
+                factsheet.project_path(),
+                factsheet.external_urls.as_deref().unwrap_or_default(),
+            ))
+            && build_project(factsheet.project_path(), cancel)
+                .await?
+                .status
+                .success()
+            && self.run_gates(factsheet, cancel).await?.is_none()
+        {
+            let server: RunningServer = start_server(factsheet.project_path()).await?;
+            let route_checks: Vec<RouteCheck> = self.check_routes(routes, cancel).await?;
+            let lifecycle_checks: Vec<LifecycleStep> =
+                self.check_lifecycles(routes, cancel).await?;
+            let regressions: Vec<String> =
+                check_regressions(factsheet, &route_checks, &lifecycle_checks);
+            if !regressions.is_empty() {
+                PrintCommand::Issue.print_agent_message(
+                    self.attributes.position.as_str(),
+                    &format!("Optimization broke {}", regressions.join(", ")),
+                );
+                drop(server);
+                return self.restore_original(factsheet, original_code, original_response);
+            }
+            let report: LoadReport = run_load_test(
+                &client,
+                "optimized",
+                urls,
+                LOAD_TEST_REQUESTS,
+                LOAD_TEST_CONCURRENCY,
+            )
+            .await;
+            drop(server);
+            self.print_load_report(&report);
+            factsheet.load_reports.push(report.clone());
+            optimized = Some(report);
+        }
+
+        let improved: bool = optimized.is_some_and(|report| {
+            report.p99_ms < baseline.p99_ms && report.errors <= baseline.errors
+        });
+        if !improved {
+            return self.restore_original(factsheet, original_code, original_response);
+        }
+        Ok(())
+    }
+    fn restore_original(
+        &self,
+        factsheet: &mut FactSheet,
+        original_code: String,
+        original_response: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(factsheet.project_path().join(EXEC_MAIN_PATH), original_code)?;
+        factsheet.backend_code = original_response;
+        PrintCommand::Issue.print_agent_message(
+            self.attributes.position.as_str(),
+            "Optimization was not an improvement, keeping the original code",
+        );
+        Ok(())
+    }
+}
+// Route and lifecycle checks that passed for the factsheet's code but fail for the new one
+pub fn check_regressions(
+    factsheet: &FactSheet,
+    route_checks: &[RouteCheck],
+    lifecycle_checks: &[LifecycleStep],
+) -> Vec<String> {
+    let broken_routes = route_checks
+        .iter()
+        .filter(|check| {
+            !check.passed()
+                && factsheet.route_checks.iter().any(|before| {
+                    before.method == check.method && before.route == check.route && before.passed()
+                })
+        })
+        .map(|check| format!("{} {}", check.method.to_uppercase(), check.route));
+    let broken_steps = lifecycle_checks
+        .iter()
+        .filter(|step| {
+            !step.passed
+                && factsheet.lifecycle_checks.iter().any(|before| {
+                    before.resource == step.resource && before.step == step.step && before.passed
+                })
+        })
+        .map(|step| format!("{} {}", step.resource, step.step));
+    broken_routes.chain(broken_steps).collect()
+}
+impl Default for AgentBackendDeveloper {
+    fn default() -> Self {
+        Self::new()
     }
 }
+// Shape mismatches of the route and lifecycle checks, one line per field
+pub fn response_shape_bugs(
+    route_checks: &[RouteCheck],
+    lifecycle_checks: &[LifecycleStep],
+) -> Vec<String> {
+    let route_bugs = route_checks
+        .iter()
+        .map(|check| (&check.method, &check.route, &check.mismatches));
+    let lifecycle_bugs = lifecycle_checks
+        .iter()
+        .map(|step| (&step.method, &step.route, &step.mismatches));
+    route_bugs
+        .chain(lifecycle_bugs)
+        .flat_map(|(method, route, mismatches)| {
+            mismatches
+                .iter()
+                .map(move |mismatch| format!("{} {}: {}", method.to_uppercase(), route, mismatch))
+        })
+        .collect()
+}
+// Ask the model for the JSON schema of the routes in the project's main.rs
+pub async fn extract_rest_api_endpoints(
+    agent_position: &str,
+    project_dir: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let backend_code = read_exec_main_contents(project_dir);
+    // Structuring msg_context
+    let msg_context = format!("CODE_INPUT: {}", backend_code);
+    let ai_response = ai_task_request(
+        msg_context,
+        agent_position,
+        get_function_string!(print_rest_api_endpoints),
+        print_rest_api_endpoints,
+    )
+    .await?;
+
+    let code_block: String =
+        extract_code_block(ai_response).ok_or("No API endpoint schema in the AI response")?;
+    Ok(code_block.trim().to_string())
+}
 #[async_trait]
 impl SpecialFunctions for AgentBackendDeveloper {
     fn get_attributes_from_agent(&self) -> &BasicAgent {
@@ -125,18 +564,30 @@ impl SpecialFunctions for AgentBackendDeveloper {
     async fn execute(
         &mut self,
         factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
     ) -> Result<(), Box<dyn std::error::Error>> {
         while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
             match self.attributes.state {
                 AgentState::Discovery => {
-                    self.call_initial_backend_code(factsheet).await;
+                    self.call_initial_backend_code(factsheet).await?;
                     self.attributes.state = AgentState::Working;
                 }
                 AgentState::Working => {
                     if self.bug_count == 0 {
-                        self.call_improved_backend_code(factsheet).await;
+                        // Review the code against the goal, high severity findings get reworked
+                        let review: Option<CodeReview> =
+                            AgentCodeReviewer::new().review_backend(factsheet).await?;
+                        let needs_rework: bool =
+                            review.as_ref().is_some_and(CodeReview::has_high_severity);
+                        factsheet.code_review = review;
+                        if needs_rework && self.review_rounds < MAX_REVIEW_ROUNDS {
+                            self.review_rounds += 1;
+                            self.call_improved_backend_code(factsheet).await?;
+                            continue;
+                        }
                     } else {
-                        self.call_fix_code_bugs(factsheet).await;
+                        self.call_fix_code_bugs(factsheet).await?;
                     }
                     self.attributes.state = AgentState::UnitTesting;
                 }
@@ -146,47 +597,83 @@ impl SpecialFunctions for AgentBackendDeveloper {
                         self.attributes.position.as_str(),
                         "Backend Code Unit Testing : Requesting User Input",
                     );
-                    let is_safe_code = confirm_safe_code();
+                    let findings: Vec<PolicyFinding> = lint_project(
+                        factsheet.project_path(),
+                        factsheet.external_urls.as_deref().unwrap_or_default(),
+                    );
+                    let is_safe_code = confirm_safe_code(&findings);
                     if !is_safe_code {
-                        panic!("Better go work on some AI alignment");
+                        return Err("AI written backend code was not approved".into());
                     }
                     // Build and test code
                     PrintCommand::UnitTest.print_agent_message(
                         self.attributes.position.as_str(),
                         "Backend Code Unit Testing: Building Agent",
                     );
-                    let build_backend_server = Command::new("cargo")
-                        .arg("build")
-                        .current_dir(WEB_TEMPLATE_PATH)
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::piped())
-                        .output()
-                        .expect("Failed to run Backend Application");
-                    // Determine if build bug_errors
-                    if build_backend_server.status.success() {
-                        self.bug_count = 0;
+                    let build: FixedBuild =
+                        build_applying_suggestions(factsheet.project_path(), cancel).await?;
+                    factsheet.record_diagnostics(&self.attributes.position, &build.diagnostics);
+                    if build.applied > 0 {
                         PrintCommand::UnitTest.print_agent_message(
                             self.attributes.position.as_str(),
-                            "Backend Code Unit Testing: Test server build successfull..",
+                            &format!(
+                                "Backend Code Unit Testing: applied {} compiler suggestions",
+                                build.applied
+                            ),
                         );
-                        self.attributes.state = AgentState::Finished
-                    } else {
-                        let error_arr: Vec<u8> = build_backend_server.stderr;
-                        let error_str = String::from_utf8(error_arr).unwrap();
+                        factsheet.backend_code =
+                            Some(read_exec_main_contents(factsheet.project_path()));
+                    }
+                    // Build errors first, then the first gate that fails
+                    let failure: Option<(Option<Gate>, FixAttempt)> =
+                        if build.output.status.success() {
+                            self.last_compiling_code =
+                                Some(read_exec_main_contents(factsheet.project_path()));
+                            self.run_gates(factsheet, cancel).await?.map(|outcome| {
+                                let attempt: FixAttempt = FixAttempt {
+                                    attempt: self.attempts.len() + 1,
+                                    stage: outcome.gate.name().to_string(),
+                                    errors: outcome.errors,
+                                    warnings: outcome.warnings,
+                                    diagnostics: outcome.failure.unwrap_or_default(),
+                                };
+                                (Some(outcome.gate), attempt)
+                            })
+                        } else {
+                            let (errors, warnings) = count_diagnostics(&build.diagnostics);
+                            let attempt: FixAttempt = FixAttempt {
+                                attempt: self.attempts.len() + 1,
+                                stage: "build".to_string(),
+                                errors,
+                                warnings,
+                                diagnostics: build_errors(
+                                    &build.diagnostics,
+                                    &build.output.stderr,
+                                    factsheet.project_path(),
+                                ),
+                            };
+                            Some((None, attempt))
+                        };
+                    if let Some((failed_gate, attempt)) = failure {
                         self.bug_count += 1;
-                        self.bug_errors = Some(error_str);
+                        self.bug_errors = Some(attempt.diagnostics.clone());
+                        self.failed_gate = failed_gate;
+                        self.attempts.push(attempt);
 
-                        if self.bug_count > 3 {
-                            PrintCommand::Issue.print_agent_message(
-                                self.attributes.position.as_str(),
-                                "Backend Code Unit Testing: Too many bugs found in Code",
-                            );
-                            panic!("Error: Too many bugs")
+                        if self.bug_count > run_config().bug_budget {
+                            return Err(Box::new(self.give_up(factsheet)?));
                         }
                         // Pass back for rework
                         self.attributes.state = AgentState::Working;
                         continue;
                     }
+                    self.bug_count = 0;
+                    self.failed_gate = None;
+                    PrintCommand::UnitTest.print_agent_message(
+                        self.attributes.position.as_str(),
+                        "Backend Code Unit Testing: Test server build successfull..",
+                    );
+                    self.attributes.state = AgentState::Finished;
                     // fixed the issue
                     /*
                       Extract and Test
@@ -194,104 +681,95 @@ impl SpecialFunctions for AgentBackendDeveloper {
                     */
 
                     // Extract API Endpoints
-                    let api_endpoints_str: String = self.call_extract_rest_api_endpoints().await;
+                    let api_endpoints_str: String = extract_rest_api_endpoints(
+                        &self.attributes.position,
+                        factsheet.project_path(),
+                    )
+                    .await?;
                     // println!("-----GALTI SHAAYAD YAHAN HUA HAI !!!!-------");
                     // println!("{}", api_endpoints_str);
                     // Convert API Endpoints into Values
                     let api_endpoints: Vec<RouteObject> =
-                        serde_json::from_str(api_endpoints_str.as_str())
-                            .expect("Failed to decode API Endpoints");
-
-                    // Define endpoints to check
-                    let check_endpoints: Vec<RouteObject> = api_endpoints
-                        .iter()
-                        .filter(|&route_object| {
-                            route_object.method == "get" && route_object.is_route_dynamic == "false"
-                        })
-                        .cloned()
-                        .collect();
+                        serde_json::from_str(api_endpoints_str.as_str())?;
 
                     // Store API Endpoints
-                    factsheet.api_endpoint_schema = Some(check_endpoints.clone());
-
-                    // Run backend application
-                    PrintCommand::UnitTest.print_agent_message(
-                        self.attributes.position.as_str(),
-                        "Backend Code Unit Testing: Starting web server...",
-                    );
+                    factsheet.api_endpoint_schema = Some(api_endpoints.clone());
 
-                    // Execute running server
-                    let mut run_backend_server: std::process::Child = Command::new("cargo")
-                        .arg("run")
-                        .current_dir(WEB_TEMPLATE_PATH)
-                        .spawn()
-                        .expect("Failed to run backend application");
-                    // Let user know testing on server will take place soon
+                    // Run the built server on a free port, the guard kills it however this step ends
                     PrintCommand::UnitTest.print_agent_message(
                         self.attributes.position.as_str(),
-                        "Backend Code Unit Testing: Launching tests on server in 5 seconds...",
+                        "Backend Code Unit Testing: Starting web server, waiting until it is ready...",
                     );
+                    let run_backend_server: RunningServer =
+                        start_server(factsheet.project_path()).await?;
 
-                    let seconds_sleep: Duration = Duration::from_secs(5);
-                    time::sleep(seconds_sleep).await;
+                    // Call every route with a synthesized body and record how it answered
+                    factsheet.route_checks = self.check_routes(&api_endpoints, cancel).await?;
+                    factsheet.lifecycle_checks =
+                        self.check_lifecycles(&api_endpoints, cancel).await?;
 
-                    // Check status code
-                    for endpoint in check_endpoints {
-                        // Confirm url testing
-                        let testing_msg: String =
-                            format!("Testing endpoint '{}'...", endpoint.route);
-                        PrintCommand::UnitTest.print_agent_message(
+                    // Responses that don't match the declared shape go back to the fix loop
+                    let shape_bugs: Vec<String> =
+                        response_shape_bugs(&factsheet.route_checks, &factsheet.lifecycle_checks);
+                    if !shape_bugs.is_empty() {
+                        drop(run_backend_server);
+                        self.response_fix_rounds += 1;
+                        self.bug_count += 1;
+                        self.bug_errors = Some(format!(
+                            "Responses do not match the endpoint schema {}:\n{}",
+                            api_endpoints_str,
+                            shape_bugs.join("\n")
+                        ));
+                        self.attempts.push(FixAttempt {
+                            attempt: self.attempts.len() + 1,
+                            stage: "response shape".to_string(),
+                            errors: shape_bugs.len(),
+                            warnings: 0,
+                            diagnostics: shape_bugs.join("\n"),
+                        });
+                        if self.response_fix_rounds > run_config().bug_budget {
+                            return Err(Box::new(self.give_up(factsheet)?));
+                        }
+                        PrintCommand::Issue.print_agent_message(
                             self.attributes.position.as_str(),
-                            testing_msg.as_str(),
+                            "Backend Code Unit Testing: Responses don't match the schema, fixing",
                         );
-
-                        // Create client with timout
-                        let client: Client = Client::builder()
-                            .timeout(Duration::from_secs(5))
-                            .build()
-                            .unwrap();
-
-                        // Test url
-                        let url: String = format!("http://localhost:8080{}", endpoint.route);
-                        match check_status_code(&client, &url).await {
-                            Ok(status_code) => {
-                                if status_code != 200 {
-                                    let err_msg: String = format!(
-                                        "WARNING: Failed to call backend url endpoint {}",
-                                        endpoint.route
-                                    );
-                                    PrintCommand::Issue.print_agent_message(
-                                        self.attributes.position.as_str(),
-                                        err_msg.as_str(),
-                                    );
-                                } else {
-                                    println!("The backend url : {} works", endpoint.route)
-                                }
-                            }
-                            Err(e) => {
-                                // kill $(lsof -t -i:8080)
-                                run_backend_server
-                                    .kill()
-                                    .expect("Failed to kill backend web server");
-                                let err_msg: String = format!("Error checking backend {}", e);
-                                PrintCommand::Issue.print_agent_message(
-                                    self.attributes.position.as_str(),
-                                    err_msg.as_str(),
-                                );
-                            }
-                        }
+                        self.attributes.state = AgentState::Working;
+                        continue;
                     }
+                    let load_test_urls: Vec<String> = factsheet
+                        .route_checks
+                        .iter()
+                        .filter(|check| {
+                            check.method == "get" && !check.route.contains('{') && check.passed()
+                        })
+                        .map(|check| server_url(&check.route))
+                        .collect();
 
-                    save_api_endpoints(&api_endpoints_str);
+                    save_api_endpoints(factsheet.project_path(), &api_endpoints_str);
+                    save_openapi_document(
+                        factsheet.project_path(),
+                        &factsheet.project_description,
+                        &api_endpoints,
+                    );
 
                     PrintCommand::UnitTest.print_agent_message(
                         self.attributes.position.as_str(),
                         "Backend testing complete...",
                     );
 
-                    run_backend_server
-                        .kill()
-                        .expect("Failed to kill backend web server on completion");
+                    if run_config().load_test && !load_test_urls.is_empty() {
+                        self.run_load_tests(
+                            factsheet,
+                            &api_endpoints,
+                            &load_test_urls,
+                            run_backend_server,
+                            cancel,
+                        )
+                        .await?;
+                    } else {
+                        drop(run_backend_server);
+                    }
 
                     self.attributes.state = AgentState::Finished;
                 }
@@ -306,6 +784,66 @@ impl SpecialFunctions for AgentBackendDeveloper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::general::create_project_dir;
+    #[test]
+    fn tests_collects_response_shape_bugs() {
+        let route_checks: Vec<RouteCheck> = vec![RouteCheck {
+            method: "get".to_string(),
+            route: "/song".to_string(),
+            status: Some(200),
+            error: None,
+            mismatches: vec!["missing field `rank`".to_string()],
+        }];
+        let lifecycle_checks: Vec<LifecycleStep> = vec![LifecycleStep {
+            resource: "/song".to_string(),
+            step: "read".to_string(),
+            method: "get".to_string(),
+            route: "/song/{id}".to_string(),
+            status: Some(200),
+            passed: false,
+            error: None,
+            mismatches: vec!["`id` should be a number but is string".to_string()],
+        }];
+        assert_eq!(
+            response_shape_bugs(&route_checks, &lifecycle_checks),
+            vec![
+                "GET /song: missing field `rank`",
+                "GET /song/{id}: `id` should be a number but is string",
+            ]
+        );
+    }
+    #[test]
+    fn tests_finds_regressions() {
+        let check = |route: &str, status: u16| RouteCheck {
+            method: "get".to_string(),
+            route: route.to_string(),
+            status: Some(status),
+            error: None,
+            mismatches: vec![],
+        };
+        let mut factsheet: FactSheet = serde_json::from_str(
+            r#"{"project_description": "", "project_scope": null, "external_urls": null, "backend_code": null, "api_endpoint_schema": null}"#,
+        )
+        .unwrap();
+        factsheet.route_checks = vec![check("/song", 200), check("/album", 500)];
+        // Only a route that worked before counts, the already failing one doesn't
+        assert_eq!(
+            check_regressions(
+                &factsheet,
+                &[check("/song", 500), check("/album", 500)],
+                &[]
+            ),
+            vec!["GET /song"]
+        );
+        assert!(
+            check_regressions(
+                &factsheet,
+                &[check("/song", 200), check("/album", 200)],
+                &[]
+            )
+            .is_empty()
+        );
+    }
     #[tokio::test]
     async fn tests_writing_backend_code() {
         let mut agent = AgentBackendDeveloper::new();
@@ -323,9 +861,11 @@ mod tests {
     "api_endpoint_schema": null
 }"#;
         let mut factsheet: FactSheet = serde_json::from_str(factsheet_str).unwrap();
+        factsheet.project_dir =
+            Some(create_project_dir(&std::env::temp_dir().join("backendbro-tests")).unwrap());
         dbg!(&factsheet);
         agent
-            .execute(&mut factsheet)
+            .execute(&mut factsheet, &CancellationToken::new())
             .await
             .expect("Failed to execute Backend Developer code");
     }
@@ -0,0 +1,209 @@
+use crate::ai_functions::ai_func_clarifier::print_clarifying_questions;
+use crate::helpers::command_line::{PrintCommand, get_user_response};
+use crate::helpers::config::{RunConfig, run_config};
+use crate::helpers::general::{ai_task_request, extract_code_block};
+use crate::helpers::process::check_cancelled;
+use crate::models::agents::agents_traits::{FactSheet, SpecialFunctions};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::io::{IsTerminal, stdin};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ClarifyingQuestion {
+    pub topic: String,
+    pub question: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ClarifyingQuestions {
+    questions: Vec<ClarifyingQuestion>,
+}
+
+// Requirements Clarifier
+#[derive(Debug)]
+pub struct AgentRequirementsClarifier {
+    attributes: BasicAgent,
+}
+
+impl AgentRequirementsClarifier {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Asks the user about whatever the request leaves open before scoping"
+                .to_string(),
+            position: "Requirements Clarifier".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self { attributes }
+    }
+
+    async fn call_clarifying_questions(
+        &self,
+        factsheet: &FactSheet,
+        max_questions: usize,
+    ) -> Result<Vec<ClarifyingQuestion>, Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "USER_REQUEST: {} \n PROJECT_DESCRIPTION: {} \n MAX_QUESTIONS: {}",
+            factsheet.user_request.as_deref().unwrap_or_default(),
+            factsheet.project_description,
+            max_questions
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_clarifying_questions),
+            print_clarifying_questions,
+        )
+        .await?;
+        match parse_questions(ai_response, max_questions) {
+            Ok(questions) => Ok(questions),
+            Err(err) => {
+                let err_msg: String = format!("Could not read clarifying questions: {}", err);
+                PrintCommand::Issue
+                    .print_agent_message(self.attributes.position.as_str(), &err_msg);
+                Ok(vec![])
+            }
+        }
+    }
+}
+
+impl Default for AgentRequirementsClarifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Questions from the model, capped at max_questions
+pub fn parse_questions(
+    ai_response: String,
+    max_questions: usize,
+) -> Result<Vec<ClarifyingQuestion>, String> {
+    let json: String = extract_code_block(ai_response.clone()).unwrap_or(ai_response);
+    let mut questions: Vec<ClarifyingQuestion> =
+        serde_json::from_str::<ClarifyingQuestions>(json.trim())
+            .map_err(|e| e.to_string())?
+            .questions;
+    questions.truncate(max_questions);
+    Ok(questions)
+}
+
+// Scripted runs (--yes) and runs without a terminal on stdin are never stopped for questions
+pub fn should_ask_questions(config: &RunConfig, stdin_is_terminal: bool) -> bool {
+    config.max_questions > 0 && !config.auto_approve && stdin_is_terminal
+}
+
+// Appends every answered question to the description the Solutions Architect scopes from
+pub fn fold_answers(project_description: &str, answers: &[(ClarifyingQuestion, String)]) -> String {
+    let clarifications: Vec<String> = answers
+        .iter()
+        .filter(|(_, answer)| !answer.trim().is_empty())
+        .map(|(question, answer)| {
+            format!(
+                "- {}: {} {}",
+                question.topic,
+                question.question,
+                answer.trim()
+            )
+        })
+        .collect();
+    if clarifications.is_empty() {
+        return project_description.to_string();
+    }
+    format!(
+        "{}\nClarified with the user:\n{}",
+        project_description.trim(),
+        clarifications.join("\n")
+    )
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentRequirementsClarifier {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let max_questions: usize = run_config().max_questions;
+        if !should_ask_questions(run_config(), stdin().is_terminal()) {
+            self.attributes.state = AgentState::Finished;
+            return Ok(());
+        }
+
+        let questions: Vec<ClarifyingQuestion> = self
+            .call_clarifying_questions(factsheet, max_questions)
+            .await?;
+        if questions.is_empty() {
+            PrintCommand::AICall.print_agent_message(
+                self.attributes.position.as_str(),
+                "Request is clear, no questions needed",
+            );
+        }
+
+        let mut answers: Vec<(ClarifyingQuestion, String)> = vec![];
+        for question in questions {
+            check_cancelled(cancel)?;
+            let answer: String =
+                get_user_response(&format!("{} (Enter to skip)", question.question));
+            answers.push((question, answer));
+        }
+        factsheet.project_description = fold_answers(&factsheet.project_description, &answers);
+        self.attributes.state = AgentState::Finished;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_folds_answers_into_description() {
+        let questions: Vec<ClarifyingQuestion> = parse_questions(
+            r#"{"questions": [
+                {"topic": "entities", "question": "What is stored for each song?"},
+                {"topic": "auth", "question": "Do users need an account?"},
+                {"topic": "persistence", "question": "Must songs survive a restart?"}
+            ]}"#
+            .to_string(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(questions.len(), 2);
+
+        let answers: Vec<(ClarifyingQuestion, String)> = vec![
+            (questions[0].clone(), " Title, artist and rank ".to_string()),
+            (questions[1].clone(), String::new()),
+        ];
+        assert_eq!(
+            fold_answers("build a website that ranks songs", &answers),
+            "build a website that ranks songs\nClarified with the user:\n- entities: What is stored for each song? Title, artist and rank"
+        );
+        assert_eq!(fold_answers("build a todo app", &[]), "build a todo app");
+    }
+
+    #[test]
+    fn tests_scripted_runs_never_prompt() {
+        let interactive: RunConfig = RunConfig::default();
+        assert!(should_ask_questions(&interactive, true));
+        assert!(!should_ask_questions(&interactive, false));
+
+        let scripted: RunConfig = RunConfig {
+            auto_approve: true,
+            ..RunConfig::default()
+        };
+        assert!(!should_ask_questions(&scripted, true));
+
+        let no_questions: RunConfig = RunConfig {
+            max_questions: 0,
+            ..RunConfig::default()
+        };
+        assert!(!should_ask_questions(&no_questions, true));
+    }
+}
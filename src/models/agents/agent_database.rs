@@ -0,0 +1,376 @@
+use crate::ai_functions::ai_func_database::print_data_model;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{add_project_dependency, ai_task_request, extract_code_block};
+use crate::helpers::process::check_cancelled;
+use crate::models::agents::agents_traits::{DataModel, Entity, FactSheet, SpecialFunctions};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+pub const MIGRATIONS_DIR: &str = "migrations";
+pub const INITIAL_MIGRATION: &str = "0001_init.sql";
+// Same major version as ours, so check_migration runs the SQLite the generated project gets
+const SQLITE_DEPENDENCY: &str = "rusqlite = { version = \"0.40\", features = [\"bundled\"] }";
+
+// Database Designer
+#[derive(Debug)]
+pub struct AgentDatabaseDesigner {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+}
+
+impl AgentDatabaseDesigner {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Designs the SQLite schema and migrations for the website data".to_string(),
+            position: "Database Designer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+        }
+    }
+
+    async fn call_data_model(
+        &mut self,
+        factsheet: &FactSheet,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let msg_context: String = match &self.bug_errors {
+            Some(errors) => format!(
+                "PROJECT_DESCRIPTION: {} \n ERRORS: {}",
+                factsheet.project_description, errors
+            ),
+            None => format!("PROJECT_DESCRIPTION: {}", factsheet.project_description),
+        };
+        ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_data_model),
+            print_data_model,
+        )
+        .await
+    }
+
+    // Write the migration and wire rusqlite into the generated project
+    fn save_migrations(&self, project_dir: &Path, migration: &str) -> std::io::Result<()> {
+        let migrations_dir: PathBuf = project_dir.join(MIGRATIONS_DIR);
+        fs::create_dir_all(&migrations_dir)?;
+        fs::write(migrations_dir.join(INITIAL_MIGRATION), migration)?;
+
+        let manifest_path: PathBuf = project_dir.join("Cargo.toml");
+        let manifest: String = fs::read_to_string(&manifest_path)?;
+        if let Some(manifest) = add_project_dependency(&manifest, SQLITE_DEPENDENCY) {
+            fs::write(manifest_path, manifest)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for AgentDatabaseDesigner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Data model in the response, fenced or bare JSON
+pub fn parse_data_model(ai_response: String) -> Result<DataModel, String> {
+    let json: String = extract_code_block(ai_response.clone()).unwrap_or(ai_response);
+    serde_json::from_str::<DataModel>(json.trim()).map_err(|e| e.to_string())
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn sql_type(field_type: &str) -> Option<&'static str> {
+    match field_type {
+        "integer" | "boolean" => Some("INTEGER"),
+        "real" => Some("REAL"),
+        "text" => Some("TEXT"),
+        _ => None,
+    }
+}
+
+// Catches what the model gets wrong before any SQL is written
+pub fn validate_data_model(data_model: &DataModel) -> Result<(), Vec<String>> {
+    let mut errors: Vec<String> = vec![];
+    if data_model.entities.is_empty() {
+        errors.push("data model has no entities".to_string());
+    }
+    for entity in &data_model.entities {
+        if !is_identifier(&entity.name) {
+            errors.push(format!("entity '{}' is not a snake_case name", entity.name));
+        }
+        let primary_keys: usize = entity.fields.iter().filter(|f| f.primary_key).count();
+        if primary_keys != 1 {
+            errors.push(format!(
+                "entity '{}' has {} primary keys, expected exactly 1",
+                entity.name, primary_keys
+            ));
+        }
+        for field in &entity.fields {
+            if !is_identifier(&field.name) {
+                errors.push(format!(
+                    "field '{}.{}' is not a snake_case name",
+                    entity.name, field.name
+                ));
+            }
+            if sql_type(&field.field_type).is_none() {
+                errors.push(format!(
+                    "field '{}.{}' has unknown type '{}'",
+                    entity.name, field.name, field.field_type
+                ));
+            }
+            if let Some(target) = &field.references {
+                // Foreign keys are written as REFERENCES <target>(id)
+                match data_model.entities.iter().find(|e| &e.name == target) {
+                    None => errors.push(format!(
+                        "field '{}.{}' references unknown entity '{}'",
+                        entity.name, field.name, target
+                    )),
+                    Some(target_entity)
+                        if !target_entity
+                            .fields
+                            .iter()
+                            .any(|f| f.primary_key && f.name == "id") =>
+                    {
+                        errors.push(format!(
+                            "field '{}.{}' references '{}', which has no 'id' primary key",
+                            entity.name, field.name, target
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn create_table_sql(entity: &Entity) -> String {
+    let columns: Vec<String> = entity
+        .fields
+        .iter()
+        .map(|field| {
+            let mut column: String = format!(
+                "    {} {}",
+                field.name,
+                sql_type(&field.field_type).unwrap_or("TEXT")
+            );
+            if field.primary_key {
+                column.push_str(" PRIMARY KEY");
+            } else if !field.nullable {
+                column.push_str(" NOT NULL");
+            }
+            if let Some(target) = &field.references {
+                column.push_str(&format!(" REFERENCES {}(id)", target));
+            }
+            column
+        })
+        .collect();
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n",
+        entity.name,
+        columns.join(",\n")
+    )
+}
+
+// SQL for the initial migration, one table per entity
+pub fn migration_sql(data_model: &DataModel) -> String {
+    data_model
+        .entities
+        .iter()
+        .map(create_table_sql)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Apply the migration to an in memory database so broken SQL never reaches the backend
+pub fn check_migration(migration: &str) -> Result<(), String> {
+    let connection: Connection = Connection::open_in_memory().map_err(|e| e.to_string())?;
+    connection
+        .execute_batch(migration)
+        .map_err(|e| e.to_string())
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentDatabaseDesigner {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let is_crud_required: bool = factsheet
+            .project_scope
+            .as_ref()
+            .is_some_and(|scope| scope.is_crud_required);
+        if !is_crud_required {
+            PrintCommand::AICall.print_agent_message(
+                self.attributes.position.as_str(),
+                "No CRUD required, skipping database design",
+            );
+            self.attributes.state = AgentState::Finished;
+        }
+
+        let mut data_model: Option<DataModel> = None;
+        while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
+            match self.attributes.state {
+                AgentState::Discovery | AgentState::Working => {
+                    let ai_response: String = self.call_data_model(factsheet).await?;
+                    match parse_data_model(ai_response) {
+                        Ok(model) => {
+                            data_model = Some(model);
+                            self.attributes.state = AgentState::UnitTesting;
+                        }
+                        Err(err) => {
+                            self.bug_errors = Some(format!("Output was not valid JSON: {}", err));
+                            self.bug_count += 1;
+                        }
+                    }
+                }
+                AgentState::UnitTesting => {
+                    let mut model: DataModel = data_model.take().unwrap();
+                    let migration: String = migration_sql(&model);
+                    let checked: Result<(), String> = validate_data_model(&model)
+                        .map_err(|errors| errors.join("; "))
+                        .and_then(|_| check_migration(&migration));
+
+                    match checked {
+                        Ok(()) => {
+                            self.save_migrations(factsheet.project_path(), &migration)?;
+                            model.migrations = vec![migration];
+                            let done_msg: String = format!(
+                                "Data model ready with tables: {}",
+                                model
+                                    .entities
+                                    .iter()
+                                    .map(|e| e.name.as_str())
+                                    .collect::<Vec<&str>>()
+                                    .join(", ")
+                            );
+                            PrintCommand::UnitTest
+                                .print_agent_message(self.attributes.position.as_str(), &done_msg);
+                            factsheet.data_model = Some(model);
+                            self.attributes.state = AgentState::Finished;
+                        }
+                        Err(err) => {
+                            let err_msg: String = format!("Data model rejected: {}", err);
+                            PrintCommand::Issue
+                                .print_agent_message(self.attributes.position.as_str(), &err_msg);
+                            self.bug_errors = Some(err);
+                            self.bug_count += 1;
+                            self.attributes.state = AgentState::Working;
+                        }
+                    }
+                }
+                _ => {
+                    self.attributes.state = AgentState::Finished;
+                }
+            }
+
+            // Fall back to the backend's own storage rather than loop on a bad model
+            if self.bug_count > 2 {
+                PrintCommand::Issue.print_agent_message(
+                    self.attributes.position.as_str(),
+                    "Could not design a valid data model, the backend keeps its own storage",
+                );
+                self.attributes.state = AgentState::Finished;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_model() -> DataModel {
+        serde_json::from_str(
+            r#"{
+              "entities": [
+                {
+                  "name": "artists",
+                  "fields": [
+                    { "name": "id", "field_type": "integer", "primary_key": true },
+                    { "name": "name", "field_type": "text" }
+                  ]
+                },
+                {
+                  "name": "songs",
+                  "fields": [
+                    { "name": "id", "field_type": "integer", "primary_key": true },
+                    { "name": "title", "field_type": "text" },
+                    { "name": "explicit", "field_type": "boolean", "nullable": true },
+                    { "name": "artist_id", "field_type": "integer", "references": "artists" }
+                  ]
+                }
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tests_migration_applies_to_sqlite() {
+        let model: DataModel = data_model();
+        assert!(validate_data_model(&model).is_ok());
+        let migration: String = migration_sql(&model);
+        assert!(migration.contains("artist_id INTEGER NOT NULL REFERENCES artists(id)"));
+        assert!(migration.contains("explicit INTEGER,"));
+        assert!(check_migration(&migration).is_ok());
+    }
+
+    #[test]
+    fn tests_rejects_invalid_data_model() {
+        let mut model: DataModel = data_model();
+        model.entities[1].fields[0].primary_key = false;
+        model.entities[1].fields[3].references = Some("albums".to_string());
+        model.entities[0].fields[1].field_type = "varchar".to_string();
+        let errors: Vec<String> = validate_data_model(&model).unwrap_err();
+        assert_eq!(errors.len(), 3);
+
+        let mut model: DataModel = data_model();
+        model.entities[0].fields[0].name = "artist_key".to_string();
+        assert_eq!(
+            validate_data_model(&model).unwrap_err(),
+            vec!["field 'songs.artist_id' references 'artists', which has no 'id' primary key"]
+        );
+    }
+
+    #[test]
+    fn tests_generated_sqlite_matches_ours() {
+        // Below 1.0 the minor version is the breaking one
+        let compatible =
+            |version: &str| version.split('.').take(2).collect::<Vec<&str>>().join(".");
+        let manifest: toml::Value = toml::from_str(include_str!("../../../Cargo.toml")).unwrap();
+        let generated: toml::Value = toml::from_str(SQLITE_DEPENDENCY).unwrap();
+        assert_eq!(
+            compatible(
+                manifest["dependencies"]["rusqlite"]["version"]
+                    .as_str()
+                    .unwrap()
+            ),
+            compatible(generated["rusqlite"]["version"].as_str().unwrap())
+        );
+    }
+}
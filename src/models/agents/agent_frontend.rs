@@ -0,0 +1,331 @@
+use crate::ai_functions::ai_func_frontend::{
+    print_fixed_frontend_code, print_frontend_client_code,
+};
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{
+    EXEC_MAIN_PATH, add_project_dependency, ai_task_request, extract_code_block,
+};
+use crate::helpers::process::check_cancelled;
+use crate::helpers::server::build_project;
+use crate::models::agents::agents_traits::{FactSheet, RouteObject, SpecialFunctions};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+pub const FRONTEND_PATH: &str = "static/index.html";
+// Where the generated server exposes the client
+pub const FRONTEND_MOUNT: &str = "/app";
+const FRONTEND_DEPENDENCY: &str = "actix-files = \"0.6\"";
+// Rewrites of the client after a failed smoke test before the step fails
+const MAX_FRONTEND_FIXES: u8 = 2;
+
+// Frontend Developer
+#[derive(Debug)]
+pub struct AgentFrontendDeveloper {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+}
+
+impl AgentFrontendDeveloper {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Builds a static web client for the generated backend".to_string(),
+            position: "Frontend Developer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+        }
+    }
+
+    fn save_frontend_code(&self, factsheet: &mut FactSheet, ai_response: String) {
+        let html: String = match extract_code_block(ai_response.clone()) {
+            Some(code) => code.trim().to_string(),
+            None => ai_response,
+        };
+        let frontend_path: PathBuf = factsheet.project_path().join(FRONTEND_PATH);
+        fs::create_dir_all(frontend_path.parent().unwrap()).expect("Failed to create static dir");
+        fs::write(&frontend_path, &html).expect("Failed to write index.html");
+        factsheet.frontend_code = Some(html);
+    }
+
+    async fn call_frontend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "PROJECT_DESCRIPTION: {} \n API_ENDPOINT_SCHEMA: {}",
+            factsheet.project_description,
+            serde_json::to_string(&factsheet.api_endpoint_schema).unwrap()
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_frontend_client_code),
+            print_frontend_client_code,
+        )
+        .await?;
+        self.save_frontend_code(factsheet, ai_response);
+        Ok(())
+    }
+
+    async fn call_fix_frontend_bugs(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "HTML_CLIENT: {:?} \n API_ENDPOINT_SCHEMA: {} \n ERRORS: {:?} \n
+                THIS FUNCTION ONLY OUTPUTS CODE. JUST OUTPUT THE CODE",
+            factsheet.frontend_code,
+            serde_json::to_string(&factsheet.api_endpoint_schema).unwrap(),
+            self.bug_errors
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_fixed_frontend_code),
+            print_fixed_frontend_code,
+        )
+        .await?;
+        self.save_frontend_code(factsheet, ai_response);
+        Ok(())
+    }
+
+    // Mount static/ on the generated server, rolled back if the server stops compiling
+    async fn serve_frontend(
+        &self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let project_dir: &Path = factsheet.project_path();
+        let main_path: PathBuf = project_dir.join(EXEC_MAIN_PATH);
+        let manifest_path: PathBuf = project_dir.join("Cargo.toml");
+        let main_code: String = fs::read_to_string(&main_path)?;
+        let manifest: String = fs::read_to_string(&manifest_path)?;
+
+        let (Some(served_code), Some(served_manifest)) = (
+            inject_static_service(&main_code),
+            add_project_dependency(&manifest, FRONTEND_DEPENDENCY),
+        ) else {
+            return Ok(false);
+        };
+        fs::write(&main_path, &served_code)?;
+        fs::write(&manifest_path, served_manifest)?;
+
+        let build: std::process::Output = build_project(project_dir, cancel).await?;
+        if !build.status.success() {
+            fs::write(&main_path, main_code)?;
+            fs::write(&manifest_path, manifest)?;
+            return Ok(false);
+        }
+        factsheet.backend_code = Some(served_code);
+        Ok(true)
+    }
+}
+
+impl Default for AgentFrontendDeveloper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Urls passed to fetch(), template literal placeholders are kept as ${...}
+pub fn extract_fetch_targets(html: &str) -> Vec<String> {
+    let mut targets: Vec<String> = vec![];
+    let mut rest: &str = html;
+    while let Some(start) = rest.find("fetch(") {
+        rest = rest[start + "fetch(".len()..].trim_start();
+        let target: String = match rest.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => match rest[1..].find(quote) {
+                Some(end) => rest[1..end + 1].to_string(),
+                None => rest.to_string(),
+            },
+            // Anything but a literal can not be checked against the schema
+            _ => rest
+                .split([',', ')'])
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        targets.push(target);
+    }
+    targets
+}
+
+// Whether a concrete or templated path can hit the given route, {id} matches any segment
+pub fn route_matches(route: &str, target: &str) -> bool {
+    let path: &str = target.split(['?', '#']).next().unwrap_or_default();
+    let route_segments: Vec<&str> = route.trim_matches('/').split('/').collect();
+    let path_segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    route_segments.len() == path_segments.len()
+        && route_segments
+            .iter()
+            .zip(path_segments)
+            .all(|(route_seg, path_seg)| {
+                (route_seg.starts_with('{') && route_seg.ends_with('}') && !path_seg.is_empty())
+                    || *route_seg == path_seg
+                    || (path_seg.starts_with("${") && path_seg.ends_with('}'))
+            })
+}
+
+// Fetch calls that do not target any route in the schema
+pub fn unknown_fetch_targets(html: &str, routes: &[RouteObject]) -> Vec<String> {
+    extract_fetch_targets(html)
+        .into_iter()
+        .filter(|target| {
+            !target.starts_with('/')
+                || !routes
+                    .iter()
+                    .any(|route_object| route_matches(&route_object.route, target))
+        })
+        .collect()
+}
+
+// Adds a static file service right after App::new()
+pub fn inject_static_service(main_code: &str) -> Option<String> {
+    if main_code.contains("actix_files::Files") {
+        return Some(main_code.to_string());
+    }
+    let app_start: usize = main_code.find("App::new()")? + "App::new()".len();
+    let service: String = format!(
+        "\n            .service(actix_files::Files::new(\"{}\", \"./static\").index_file(\"index.html\"))",
+        FRONTEND_MOUNT
+    );
+    Some(format!(
+        "{}{}{}",
+        &main_code[..app_start],
+        service,
+        &main_code[app_start..]
+    ))
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentFrontendDeveloper {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if factsheet.api_endpoint_schema.is_none() {
+            return Err(
+                "Frontend Developer needs an api_endpoint_schema, run the backend first".into(),
+            );
+        }
+
+        while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    self.call_frontend_code(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    self.call_fix_frontend_bugs(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    // Smoke test, every fetch must target a known route
+                    let html: &str = factsheet.frontend_code.as_deref().unwrap_or_default();
+                    let routes: &Vec<RouteObject> = factsheet.api_endpoint_schema.as_ref().unwrap();
+                    let unknown_targets: Vec<String> = unknown_fetch_targets(html, routes);
+
+                    if !unknown_targets.is_empty() {
+                        self.bug_count += 1;
+                        let err_msg: String = format!(
+                            "Frontend smoke test: fetch calls outside the schema: {}",
+                            unknown_targets.join(", ")
+                        );
+                        PrintCommand::Issue
+                            .print_agent_message(self.attributes.position.as_str(), &err_msg);
+                        if self.bug_count > MAX_FRONTEND_FIXES {
+                            // A client calling unknown routes is not mounted on the backend
+                            return Err(err_msg.into());
+                        }
+                        self.bug_errors = Some(err_msg);
+                        self.attributes.state = AgentState::Working;
+                        continue;
+                    } else {
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Frontend smoke test: every fetch call targets a schema route",
+                        );
+                    }
+
+                    let served: bool = self.serve_frontend(factsheet, cancel).await?;
+                    let serve_msg: String = if served {
+                        format!("Frontend served by the backend at {}/", FRONTEND_MOUNT)
+                    } else {
+                        format!(
+                            "Could not mount the frontend, open {} directly",
+                            FRONTEND_PATH
+                        )
+                    };
+                    PrintCommand::UnitTest
+                        .print_agent_message(self.attributes.position.as_str(), &serve_msg);
+                    self.attributes.state = AgentState::Finished;
+                }
+                _ => {
+                    self.attributes.state = AgentState::Finished;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route: &str, method: &str) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: route.contains('{').to_string(),
+            method: method.to_string(),
+            request_body: serde_json::Value::String("None".to_string()),
+            response: serde_json::Value::String("None".to_string()),
+            route: route.to_string(),
+        }
+    }
+
+    #[test]
+    fn tests_fetch_targets_match_schema() {
+        let html: &str = r#"
+            <script>
+              fetch("/song").then(r => r.json());
+              fetch(`/song/${id}`, { method: "DELETE" });
+              fetch('/songs?limit=5');
+              fetch(url);
+            </script>"#;
+        assert_eq!(
+            extract_fetch_targets(html),
+            vec!["/song", "/song/${id}", "/songs?limit=5", "url"]
+        );
+        let routes: Vec<RouteObject> = vec![route("/song", "get"), route("/song/{id}", "delete")];
+        assert_eq!(
+            unknown_fetch_targets(html, &routes),
+            vec!["/songs?limit=5", "url"]
+        );
+    }
+
+    #[test]
+    fn tests_injects_static_service() {
+        let main_code: &str =
+            "HttpServer::new(move || {\n        App::new()\n            .app_data(data.clone())";
+        let served: String = inject_static_service(main_code).unwrap();
+        assert!(
+            served.contains("App::new()\n            .service(actix_files::Files::new(\"/app\"")
+        );
+        assert_eq!(inject_static_service(&served).unwrap(), served);
+    }
+}
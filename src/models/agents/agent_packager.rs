@@ -0,0 +1,519 @@
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::EXEC_MAIN_PATH;
+use crate::helpers::process::check_cancelled;
+use crate::helpers::server::{HOST_ENV, PORT_ENV, binary_name, build_release};
+use crate::models::agents::agent_database::MIGRATIONS_DIR;
+use crate::models::agents::agents_traits::{FactSheet, SpecialFunctions};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use syn::visit::{self, Visit};
+use tokio_util::sync::CancellationToken;
+
+pub const DOCKERFILE_PATH: &str = "Dockerfile";
+pub const DOCKERIGNORE_PATH: &str = ".dockerignore";
+pub const ENV_TEMPLATE_PATH: &str = ".env.example";
+const DOCKERIGNORE: &str = "target/
+.env
+database.json
+database.db
+checkpoint.json
+factsheet.review.json
+";
+
+// Host the server has to listen on inside a container
+const CONTAINER_HOST: &str = "0.0.0.0";
+
+// Address the generated server binds to, as written in main.rs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerBinding {
+    pub host: String,
+    pub port: u16,
+    // Environment variable the host is read from, `host` is its default
+    pub host_env: Option<String>,
+}
+
+#[derive(Default)]
+struct ServerVisitor {
+    binding: Option<ServerBinding>,
+    env_vars: Vec<String>,
+    // let port = ...unwrap_or(8080) defaults, so a port read from the environment still resolves
+    port_defaults: HashMap<String, u16>,
+    // let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()) as (variable, default)
+    host_defaults: HashMap<String, (String, String)>,
+}
+
+// String literals of an expression in source order
+#[derive(Default)]
+struct LitStrs(Vec<String>);
+
+impl<'ast> Visit<'ast> for LitStrs {
+    fn visit_lit_str(&mut self, lit: &'ast syn::LitStr) {
+        self.0.push(lit.value());
+    }
+}
+
+fn first_lit_str(expr: &syn::Expr) -> Option<String> {
+    let mut lits: LitStrs = LitStrs::default();
+    lits.visit_expr(expr);
+    lits.0.into_iter().next()
+}
+
+// Variable an expression like `host`, `&host` or `host.as_str()` reads
+fn expr_ident(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
+        syn::Expr::Reference(reference) => expr_ident(&reference.expr),
+        syn::Expr::MethodCall(call) => expr_ident(&call.receiver),
+        syn::Expr::Paren(paren) => expr_ident(&paren.expr),
+        _ => None,
+    }
+}
+
+fn lit_str(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+fn lit_int(expr: &syn::Expr) -> Option<u16> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) => lit.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+impl<'ast> Visit<'ast> for ServerVisitor {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method == "bind"
+            && self.binding.is_none()
+            && let Some(arg) = call.args.first()
+        {
+            // .bind("127.0.0.1:8080"), .bind(("127.0.0.1", 8080)) or .bind((host.as_str(), port))
+            self.binding = match arg {
+                syn::Expr::Tuple(tuple) if tuple.elems.len() == 2 => {
+                    let port: Option<u16> = lit_int(&tuple.elems[1]).or_else(|| {
+                        let name: String = expr_ident(&tuple.elems[1])?;
+                        self.port_defaults.get(&name).copied()
+                    });
+                    let host: Option<(String, Option<String>)> = match lit_str(&tuple.elems[0]) {
+                        Some(host) => Some((host, None)),
+                        None => expr_ident(&tuple.elems[0])
+                            .and_then(|name| self.host_defaults.get(&name))
+                            .map(|(var, host)| (host.clone(), Some(var.clone()))),
+                    };
+                    match (host, port) {
+                        (Some((host, host_env)), Some(port)) => Some(ServerBinding {
+                            host,
+                            port,
+                            host_env,
+                        }),
+                        _ => None,
+                    }
+                }
+                _ => lit_str(arg).and_then(|address| {
+                    let (host, port) = address.rsplit_once(':')?;
+                    Some(ServerBinding {
+                        host: host.to_string(),
+                        port: port.parse().ok()?,
+                        host_env: None,
+                    })
+                }),
+            };
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        let pat: &syn::Pat = match &local.pat {
+            syn::Pat::Type(typed) => typed.pat.as_ref(),
+            pat => pat,
+        };
+        if let syn::Pat::Ident(ident) = pat
+            && let Some(init) = &local.init
+            && let syn::Expr::MethodCall(call) = init.expr.as_ref()
+            && (call.method == "unwrap_or" || call.method == "unwrap_or_else")
+            && let Some(default) = call.args.first()
+        {
+            let name: String = ident.ident.to_string();
+            if let Some(port) = lit_int(default) {
+                self.port_defaults.insert(name, port);
+            } else if let (Some(var), Some(host)) =
+                (first_lit_str(&call.receiver), first_lit_str(default))
+            {
+                self.host_defaults.insert(name, (var, host));
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(func) = call.func.as_ref()
+            && func
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "var" || segment.ident == "var_os")
+            && func
+                .path
+                .segments
+                .iter()
+                .any(|segment| segment.ident == "env")
+            && let Some(name) = call.args.first().and_then(lit_str)
+            && !self.env_vars.contains(&name)
+        {
+            self.env_vars.push(name);
+        }
+        visit::visit_expr_call(self, call);
+    }
+}
+
+// Bind address and environment variables read by the server code
+pub fn inspect_server(main_code: &str) -> Result<(Option<ServerBinding>, Vec<String>), String> {
+    let syntax: syn::File = syn::parse_file(main_code).map_err(|e| e.to_string())?;
+    let mut visitor: ServerVisitor = ServerVisitor::default();
+    visitor.visit_file(&syntax);
+    Ok((visitor.binding, visitor.env_vars))
+}
+
+pub fn dockerfile(project_dir: &Path, bin_name: &str, binding: &ServerBinding) -> String {
+    let mut sources: Vec<String> = vec!["COPY src ./src".to_string()];
+    if project_dir.join(MIGRATIONS_DIR).is_dir() {
+        sources.push(format!("COPY {} ./{}", MIGRATIONS_DIR, MIGRATIONS_DIR));
+    }
+    let manifests: &str = if project_dir.join("Cargo.lock").is_file() {
+        "Cargo.toml Cargo.lock"
+    } else {
+        "Cargo.toml"
+    };
+    // Frontend files are served from ./static at runtime
+    let assets: &str = if project_dir.join("static").is_dir() {
+        "COPY static ./static\n"
+    } else {
+        ""
+    };
+    format!(
+        "FROM rust:1 AS builder
+WORKDIR /app
+COPY {manifests} ./
+{sources}
+RUN cargo build --release
+
+FROM debian:bookworm-slim
+RUN apt-get update \\
+    && apt-get install -y --no-install-recommends ca-certificates libssl3 \\
+    && rm -rf /var/lib/apt/lists/*
+WORKDIR /app
+COPY --from=builder /app/target/release/{bin_name} /usr/local/bin/{bin_name}
+{assets}ENV {HOST_ENV}={CONTAINER_HOST} {PORT_ENV}={port}
+EXPOSE {port}
+CMD [\"{bin_name}\"]
+",
+        sources = sources.join("\n"),
+        port = binding.port,
+    )
+}
+
+pub fn env_template(bin_name: &str, binding: &ServerBinding, env_vars: &[String]) -> String {
+    let mut template: String = format!(
+        "# Environment for {}, copy to .env and pass it with docker run --env-file .env\n\
+         # The server listens on {}:{} (from src/main.rs)\n",
+        bin_name, binding.host, binding.port
+    );
+    for name in env_vars {
+        // An empty value would override the address the Dockerfile sets
+        let value: String = if binding.host_env.as_ref() == Some(name) {
+            CONTAINER_HOST.to_string()
+        } else if name == PORT_ENV {
+            binding.port.to_string()
+        } else {
+            String::new()
+        };
+        template.push_str(&format!("{}={}\n", name, value));
+    }
+    template
+}
+
+// Mismatches between a Dockerfile and the binary and address the project actually has
+pub fn check_dockerfile(dockerfile: &str, bin_name: &str, binding: &ServerBinding) -> Vec<String> {
+    let mut problems: Vec<String> = vec![];
+    let port: u16 = binding.port;
+    let binary_path: String = format!("target/release/{}", bin_name);
+    if !dockerfile.contains(&binary_path) {
+        problems.push(format!("Dockerfile does not copy {}", binary_path));
+    }
+    if !dockerfile
+        .lines()
+        .any(|line| line.trim().starts_with("CMD") && line.contains(bin_name))
+    {
+        problems.push(format!("Dockerfile does not start {}", bin_name));
+    }
+    let exposed: Vec<&str> = dockerfile
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("EXPOSE"))
+        .flat_map(str::split_whitespace)
+        .collect();
+    if !exposed.contains(&port.to_string().as_str()) {
+        problems.push(format!("Dockerfile does not expose port {}", port));
+    }
+    let host_setting: String = format!("{}={}", HOST_ENV, CONTAINER_HOST);
+    if !dockerfile
+        .lines()
+        .any(|line| line.trim().starts_with("ENV") && line.contains(&host_setting))
+    {
+        problems.push(format!("Dockerfile does not set {}", host_setting));
+    }
+    problems
+}
+
+// The Dockerfile opens the server up through HOST, so the server has to read it
+pub fn check_binding(binding: &ServerBinding) -> Option<String> {
+    (binding.host_env.as_deref() != Some(HOST_ENV)).then(|| {
+        format!(
+            "Server binds {}:{} without reading {}, so it can not be reached in the container. Bind (host.as_str(), port) with host read from {}",
+            binding.host, binding.port, HOST_ENV, HOST_ENV
+        )
+    })
+}
+
+// DevOps Engineer
+#[derive(Debug)]
+pub struct AgentPackager {
+    attributes: BasicAgent,
+}
+
+impl AgentPackager {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Packages the generated server into a container image".to_string(),
+            position: "DevOps Engineer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self { attributes }
+    }
+
+    fn report(&self, command: PrintCommand, message: &str) {
+        command.print_agent_message(self.attributes.position.as_str(), message);
+    }
+}
+
+impl Default for AgentPackager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentPackager {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        check_cancelled(cancel)?;
+        let project_dir: &Path = factsheet.project_path();
+        let manifest: String = fs::read_to_string(project_dir.join("Cargo.toml"))?;
+        let bin_name: String =
+            binary_name(&manifest).ok_or("Could not read the binary name from Cargo.toml")?;
+        let main_code: String = fs::read_to_string(project_dir.join(EXEC_MAIN_PATH))?;
+        let (binding, env_vars) = inspect_server(&main_code)?;
+
+        let mut problems: Vec<String> = vec![];
+        // Without a readable .bind(...) the files are still written for the default port
+        let binding: ServerBinding = match binding {
+            Some(binding) => {
+                problems.extend(check_binding(&binding));
+                binding
+            }
+            None => {
+                problems.push(format!(
+                    "Could not find a .bind(...) address in {}",
+                    EXEC_MAIN_PATH
+                ));
+                ServerBinding {
+                    host: CONTAINER_HOST.to_string(),
+                    port: 8080,
+                    host_env: Some(HOST_ENV.to_string()),
+                }
+            }
+        };
+
+        // Files the user already wrote are checked but never overwritten
+        let dockerfile_path = project_dir.join(DOCKERFILE_PATH);
+        if !dockerfile_path.exists() {
+            fs::write(
+                &dockerfile_path,
+                dockerfile(project_dir, &bin_name, &binding),
+            )?;
+        }
+        if !project_dir.join(DOCKERIGNORE_PATH).exists() {
+            fs::write(project_dir.join(DOCKERIGNORE_PATH), DOCKERIGNORE)?;
+        }
+        if !project_dir.join(ENV_TEMPLATE_PATH).exists() {
+            fs::write(
+                project_dir.join(ENV_TEMPLATE_PATH),
+                env_template(&bin_name, &binding, &env_vars),
+            )?;
+        }
+
+        problems.extend(check_dockerfile(
+            &fs::read_to_string(&dockerfile_path)?,
+            &bin_name,
+            &binding,
+        ));
+
+        self.report(
+            PrintCommand::UnitTest,
+            "Building the release binary the Dockerfile copies...",
+        );
+        let build: Output = build_release(project_dir, cancel).await?;
+        let release_binary: PathBuf = project_dir.join("target").join("release").join(format!(
+            "{}{}",
+            bin_name,
+            std::env::consts::EXE_SUFFIX
+        ));
+        if !build.status.success() {
+            problems.push(format!(
+                "cargo build --release failed:\n{}",
+                String::from_utf8_lossy(&build.stderr).trim()
+            ));
+        } else if !release_binary.is_file() {
+            problems.push(format!("No built binary at {}", release_binary.display()));
+        }
+        if problems.is_empty() {
+            let done_msg: String = format!(
+                "Container files ready for {}, port {}: docker build -t {} {}",
+                bin_name,
+                binding.port,
+                bin_name.replace('_', "-"),
+                project_dir.display()
+            );
+            self.report(PrintCommand::UnitTest, &done_msg);
+        }
+        for problem in &problems {
+            self.report(PrintCommand::Issue, problem);
+        }
+        if !problems.is_empty() {
+            return Err(format!("{} container checks failed", problems.len()).into());
+        }
+        self.attributes.state = AgentState::Finished;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_reads_server_binding_and_binary() {
+        let main_code: &str = r#"
+            #[actix_web::main]
+            async fn main() -> std::io::Result<()> {
+                let key = std::env::var("COINGECKO_API_KEY").unwrap_or_default();
+                HttpServer::new(move || App::new())
+                    .bind(("0.0.0.0", 3000))?
+                    .run()
+                    .await
+            }"#;
+        let (binding, env_vars) = inspect_server(main_code).unwrap();
+        assert_eq!(
+            binding,
+            Some(ServerBinding {
+                host: "0.0.0.0".to_string(),
+                port: 3000,
+                host_env: None,
+            })
+        );
+        assert_eq!(env_vars, vec!["COINGECKO_API_KEY"]);
+
+        let template_main: String = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/web_template/src/main.rs"
+        ))
+        .unwrap();
+        let (binding, env_vars) = inspect_server(&template_main).unwrap();
+        assert_eq!(
+            binding,
+            Some(ServerBinding {
+                host: "127.0.0.1".to_string(),
+                port: 8080,
+                host_env: Some("HOST".to_string()),
+            })
+        );
+        assert_eq!(env_vars, vec!["PORT", "HOST"]);
+
+        assert_eq!(
+            binary_name("[package]\nname = \"actix_web_project\"\n").as_deref(),
+            Some("actix_web_project")
+        );
+        assert_eq!(
+            binary_name("[package]\nname = \"api\"\n\n[[bin]]\nname = \"server\"\n").as_deref(),
+            Some("server")
+        );
+    }
+
+    #[test]
+    fn tests_checks_dockerfile_against_binary() {
+        let template_dir: &Path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/web_template"));
+        let binding: ServerBinding = ServerBinding {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            host_env: Some("HOST".to_string()),
+        };
+        let generated: String = dockerfile(template_dir, "actix_web_project", &binding);
+        assert!(generated.contains("COPY Cargo.toml Cargo.lock ./"));
+        assert!(generated.contains("ENV HOST=0.0.0.0 PORT=8080"));
+        assert!(check_dockerfile(&generated, "actix_web_project", &binding).is_empty());
+        let hard_coded: ServerBinding = ServerBinding {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            host_env: None,
+        };
+        assert_eq!(
+            check_dockerfile(&generated, "server", &hard_coded),
+            vec![
+                "Dockerfile does not copy target/release/server",
+                "Dockerfile does not start server",
+                "Dockerfile does not expose port 3000",
+            ]
+        );
+        assert_eq!(check_binding(&binding), None);
+        assert_eq!(
+            check_binding(&hard_coded),
+            Some("Server binds 127.0.0.1:3000 without reading HOST, so it can not be reached in the container. Bind (host.as_str(), port) with host read from HOST".to_string())
+        );
+        // A hard-coded 0.0.0.0 ignores HOST, so it is rejected as well
+        let literal_any: ServerBinding = ServerBinding {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+            host_env: None,
+        };
+        assert!(check_binding(&literal_any).is_some());
+        assert_eq!(
+            env_template(
+                "actix_web_project",
+                &binding,
+                &["PORT".to_string(), "HOST".to_string()]
+            ),
+            "# Environment for actix_web_project, copy to .env and pass it with docker run --env-file .env\n\
+             # The server listens on 127.0.0.1:8080 (from src/main.rs)\nPORT=8080\nHOST=0.0.0.0\n"
+        );
+    }
+}
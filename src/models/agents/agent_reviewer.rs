@@ -0,0 +1,129 @@
+use crate::ai_functions::ai_func_review::print_code_review;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{ai_task_request, extract_code_block};
+use crate::models::agents::agents_traits::{CodeReview, FactSheet, Severity, SpecialFunctions};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+// Code Reviewer
+#[derive(Debug)]
+pub struct AgentCodeReviewer {
+    attributes: BasicAgent,
+}
+
+impl AgentCodeReviewer {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Reviews the generated backend against the project description".to_string(),
+            position: "Code Reviewer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self { attributes }
+    }
+
+    // Review of the current backend code, None when the model answers with something unreadable
+    pub async fn review_backend(
+        &self,
+        factsheet: &FactSheet,
+    ) -> Result<Option<CodeReview>, Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "BACKEND_CODE: {} \n PROJECT_DESCRIPTION: {}",
+            factsheet.backend_code.as_deref().unwrap_or_default(),
+            factsheet.project_description
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_code_review),
+            print_code_review,
+        )
+        .await?;
+        match parse_code_review(ai_response) {
+            Ok(review) => {
+                self.print_findings(&review);
+                Ok(Some(review))
+            }
+            Err(err) => {
+                let err_msg: String = format!("Could not read the code review: {}", err);
+                PrintCommand::Issue
+                    .print_agent_message(self.attributes.position.as_str(), &err_msg);
+                Ok(None)
+            }
+        }
+    }
+
+    fn print_findings(&self, review: &CodeReview) {
+        if review.findings.is_empty() {
+            PrintCommand::UnitTest
+                .print_agent_message(self.attributes.position.as_str(), "No review findings");
+        }
+        for finding in &review.findings {
+            let finding_msg: String = format!(
+                "[{:?}] {}: {}",
+                finding.severity, finding.category, finding.description
+            );
+            let command: PrintCommand = if finding.severity == Severity::High {
+                PrintCommand::Issue
+            } else {
+                PrintCommand::UnitTest
+            };
+            command.print_agent_message(self.attributes.position.as_str(), &finding_msg);
+        }
+    }
+}
+
+impl Default for AgentCodeReviewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn parse_code_review(ai_response: String) -> Result<CodeReview, String> {
+    let json: String = extract_code_block(ai_response.clone()).unwrap_or(ai_response);
+    serde_json::from_str::<CodeReview>(json.trim()).map_err(|e| e.to_string())
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentCodeReviewer {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        _cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if factsheet.backend_code.is_none() {
+            return Err("Code Reviewer needs backend_code, run the backend first".into());
+        }
+        factsheet.code_review = self.review_backend(factsheet).await?;
+        self.attributes.state = AgentState::Finished;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parses_code_review() {
+        let ai_response: String = r#"```json
+{
+  "findings": [
+    { "severity": "medium", "category": "risky_pattern", "description": "unwrap on lock" },
+    { "severity": "high", "category": "missing_feature", "description": "no delete route" }
+  ]
+}
+```"#
+            .to_string();
+        let review: CodeReview = parse_code_review(ai_response).unwrap();
+        assert_eq!(review.findings.len(), 2);
+        assert!(review.has_high_severity());
+        assert!(parse_code_review(r#"{"findings": [{"severity": "urgent"}]}"#.into()).is_err());
+    }
+}
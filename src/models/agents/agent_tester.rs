@@ -0,0 +1,438 @@
+use crate::ai_functions::ai_func_backend::{print_fixed_code, print_fixed_code_edits};
+use crate::ai_functions::ai_func_tests::{print_fixed_integration_tests, print_integration_tests};
+use crate::helpers::command_line::{PrintCommand, confirm_safe_code};
+use crate::helpers::config::run_config;
+use crate::helpers::diagnostics::build_errors;
+use crate::helpers::general::{ai_task_request, extract_code_block, read_exec_main_contents};
+use crate::helpers::outcome::{self, BudgetExhausted};
+use crate::helpers::patch::patch_backend_code;
+use crate::helpers::policy::{PolicyFinding, lint_project};
+use crate::helpers::process::check_cancelled;
+use crate::helpers::server::{
+    FixedBuild, RunningServer, build_applying_suggestions, start_server, test_project,
+};
+use crate::models::agents::agent_auth::{AUTH_REQUIREMENTS, has_auth, run_auth_checks};
+use crate::models::agents::agent_frontend::route_matches;
+use crate::models::agents::agents_traits::{
+    FactSheet, FixAttempt, FixReport, RouteObject, SpecialFunctions,
+};
+use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+
+use async_trait::async_trait;
+use std::fs;
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+pub const INTEGRATION_TESTS_PATH: &str = "tests/api_routes.rs";
+
+// Test Engineer
+#[derive(Debug)]
+pub struct AgentTestEngineer {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+    // Whether the last failure lies in the generated tests rather than the backend
+    fix_tests: bool,
+    attempts: Vec<FixAttempt>,
+    // main.rs as the backend handed it over, it compiled
+    last_compiling_code: Option<String>,
+    // Whether a fix changed the backend, auth is checked again before finishing
+    backend_fixed: bool,
+}
+
+impl AgentTestEngineer {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent {
+            objective: "Writes cargo integration tests covering every route of the backend"
+                .to_string(),
+            position: "Test Engineer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+            fix_tests: false,
+            attempts: vec![],
+            last_compiling_code: None,
+            backend_fixed: false,
+        }
+    }
+
+    fn save_integration_tests(&self, factsheet: &mut FactSheet, ai_response: String) {
+        let tests: String = match extract_code_block(ai_response.clone()) {
+            Some(code) => code.trim().to_string(),
+            None => ai_response,
+        };
+        let tests_path: PathBuf = factsheet.project_path().join(INTEGRATION_TESTS_PATH);
+        fs::create_dir_all(tests_path.parent().unwrap()).expect("Failed to create tests dir");
+        fs::write(&tests_path, &tests).expect("Failed to write integration tests");
+        factsheet.integration_tests = Some(tests);
+    }
+
+    async fn call_integration_tests(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "BACKEND_CODE: {} \n API_ENDPOINT_SCHEMA: {}",
+            read_exec_main_contents(factsheet.project_path()),
+            serde_json::to_string(&factsheet.api_endpoint_schema).unwrap()
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_integration_tests),
+            print_integration_tests,
+        )
+        .await?;
+        self.save_integration_tests(factsheet, ai_response);
+        Ok(())
+    }
+
+    async fn call_fix_integration_tests(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let msg_context: String = format!(
+            "BACKEND_CODE: {} \n API_ENDPOINT_SCHEMA: {} \n INTEGRATION_TESTS: {:?} \n ERRORS: {:?} \n
+                THIS FUNCTION ONLY OUTPUTS CODE. JUST OUTPUT THE CODE",
+            read_exec_main_contents(factsheet.project_path()),
+            serde_json::to_string(&factsheet.api_endpoint_schema).unwrap(),
+            factsheet.integration_tests,
+            self.bug_errors
+        );
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_fixed_integration_tests),
+            print_fixed_integration_tests,
+        )
+        .await?;
+        self.save_integration_tests(factsheet, ai_response);
+        Ok(())
+    }
+
+    // Failing tests go through the same fix prompt as compile errors
+    async fn call_fix_code_bugs(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let auth: String = if has_auth(factsheet) {
+            format!("  AUTH_REQUIREMENTS: {} \n", AUTH_REQUIREMENTS)
+        } else {
+            String::new()
+        };
+        let msg_context: String = format!(
+            " BROKEN CODE :\n{}\n  API_ENDPOINT_SCHEMA: {} \n{}  ERROR_BUGS:{:?} \n  FAILING_TESTS:{:?} \n",
+            read_exec_main_contents(factsheet.project_path()),
+            serde_json::to_string(&factsheet.api_endpoint_schema)?,
+            auth,
+            self.bug_errors,
+            factsheet.integration_tests
+        );
+        let code: String = patch_backend_code(
+            factsheet.project_path(),
+            msg_context,
+            &self.attributes.position,
+            (
+                get_function_string!(print_fixed_code_edits),
+                print_fixed_code_edits,
+            ),
+            (get_function_string!(print_fixed_code), print_fixed_code),
+        )
+        .await?;
+        factsheet.backend_code = Some(code);
+        Ok(())
+    }
+
+    // A fixed backend still has to refuse anonymous calls, the auth agent's checks run again
+    async fn recheck_auth(
+        &self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if !self.backend_fixed || !has_auth(factsheet) {
+            return Ok(None);
+        }
+        PrintCommand::UnitTest.print_agent_message(
+            self.attributes.position.as_str(),
+            "Integration Tests: checking auth of the fixed backend...",
+        );
+        let build: FixedBuild =
+            build_applying_suggestions(factsheet.project_path(), cancel).await?;
+        if build.applied > 0 {
+            factsheet.backend_code = Some(read_exec_main_contents(factsheet.project_path()));
+        }
+        if !build.output.status.success() {
+            return Ok(Some(build_errors(
+                &build.diagnostics,
+                &build.output.stderr,
+                factsheet.project_path(),
+            )));
+        }
+        let routes: &[RouteObject] = factsheet.api_endpoint_schema.as_deref().unwrap_or_default();
+        let server: RunningServer = start_server(factsheet.project_path()).await?;
+        let failures: Vec<String> = run_auth_checks(routes).await;
+        drop(server);
+        Ok((!failures.is_empty()).then(|| {
+            format!(
+                "Auth checks failed after the backend fix:\n{}",
+                failures.join("\n")
+            )
+        }))
+    }
+
+    // Out of fix attempts: keeps the tests and code on disk and reports what is left
+    fn give_up(&self, factsheet: &mut FactSheet) -> std::io::Result<BudgetExhausted> {
+        outcome::give_up(
+            factsheet,
+            FixReport {
+                agent: self.attributes.position.clone(),
+                bug_budget: run_config().bug_budget,
+                last_compiling_revision: None,
+                remaining_errors: self.bug_errors.clone().unwrap_or_default(),
+                attempts: self.attempts.clone(),
+            },
+            self.last_compiling_code.as_deref(),
+        )
+    }
+}
+
+impl Default for AgentTestEngineer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// (method, uri) of every TestRequest::<method>().uri("...") in the test code
+pub fn extract_test_requests(test_code: &str) -> Vec<(String, String)> {
+    let mut requests: Vec<(String, String)> = vec![];
+    let mut rest: &str = test_code;
+    while let Some(start) = rest.find("TestRequest::") {
+        rest = &rest[start + "TestRequest::".len()..];
+        let method: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        let Some(uri_start) = rest.find(".uri(") else {
+            break;
+        };
+        let uri: &str = rest[uri_start + ".uri(".len()..].trim_start();
+        if let Some(literal) = uri.strip_prefix('"')
+            && let Some(end) = literal.find('"')
+        {
+            requests.push((method, literal[..end].to_string()));
+        }
+    }
+    requests
+}
+
+// Routes of the schema that no test request calls with the right method
+pub fn uncovered_routes(test_code: &str, routes: &[RouteObject]) -> Vec<String> {
+    let requests: Vec<(String, String)> = extract_test_requests(test_code);
+    routes
+        .iter()
+        .filter(|route_object| {
+            !requests.iter().any(|(method, uri)| {
+                method.eq_ignore_ascii_case(&route_object.method)
+                    && route_matches(&route_object.route, uri)
+            })
+        })
+        .map(|route_object| {
+            format!(
+                "{} {}",
+                route_object.method.to_uppercase(),
+                route_object.route
+            )
+        })
+        .collect()
+}
+
+// Whether cargo reports an error located in the generated test file, warnings do not count
+pub fn has_test_compile_error(stderr: &str) -> bool {
+    let mut in_error: bool = false;
+    for line in stderr.lines() {
+        if line.starts_with("error") {
+            in_error = true;
+        } else if line.starts_with("warning") {
+            in_error = false;
+        } else if in_error && line.trim_start().starts_with("-->") {
+            return line.contains(INTEGRATION_TESTS_PATH);
+        }
+    }
+    false
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentTestEngineer {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if factsheet.api_endpoint_schema.is_none() {
+            return Err("Test Engineer needs an api_endpoint_schema, run the backend first".into());
+        }
+
+        while self.attributes.state != AgentState::Finished {
+            check_cancelled(cancel)?;
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    self.last_compiling_code =
+                        Some(read_exec_main_contents(factsheet.project_path()));
+                    self.call_integration_tests(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    if self.fix_tests {
+                        self.call_fix_integration_tests(factsheet).await?;
+                    } else {
+                        self.call_fix_code_bugs(factsheet).await?;
+                        self.backend_fixed = true;
+                    }
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    let test_code: &str =
+                        factsheet.integration_tests.as_deref().unwrap_or_default();
+                    let routes: &Vec<RouteObject> = factsheet.api_endpoint_schema.as_ref().unwrap();
+                    let uncovered: Vec<String> = uncovered_routes(test_code, routes);
+
+                    let failure: Option<String> = if !uncovered.is_empty() {
+                        self.fix_tests = true;
+                        Some(format!("No test calls: {}", uncovered.join(", ")))
+                    } else {
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Integration Tests: Requesting User Input",
+                        );
+                        let findings: Vec<PolicyFinding> = lint_project(
+                            factsheet.project_path(),
+                            factsheet.external_urls.as_deref().unwrap_or_default(),
+                        );
+                        if !confirm_safe_code(&findings) {
+                            return Err("AI written code was not approved for cargo test".into());
+                        }
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Integration Tests: Running cargo test...",
+                        );
+                        let output = test_project(factsheet.project_path(), cancel).await?;
+                        if output.status.success() {
+                            self.fix_tests = false;
+                            self.recheck_auth(factsheet, cancel).await?
+                        } else {
+                            let stderr: String =
+                                String::from_utf8_lossy(&output.stderr).to_string();
+                            // Compile errors in the tests are their own fault, anything else the backend's
+                            self.fix_tests = has_test_compile_error(&stderr);
+                            Some(format!(
+                                "{}\n{}",
+                                String::from_utf8_lossy(&output.stdout),
+                                stderr
+                            ))
+                        }
+                    };
+
+                    let Some(failure) = failure else {
+                        PrintCommand::UnitTest.print_agent_message(
+                            self.attributes.position.as_str(),
+                            "Integration Tests: every route is tested and passing",
+                        );
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    };
+
+                    let err_msg: String = if self.fix_tests {
+                        "Integration Tests: fixing the generated tests".to_string()
+                    } else {
+                        "Integration Tests: tests failed, fixing the backend".to_string()
+                    };
+                    PrintCommand::Issue
+                        .print_agent_message(self.attributes.position.as_str(), &err_msg);
+                    self.attempts.push(FixAttempt {
+                        attempt: self.attempts.len() + 1,
+                        stage: if self.fix_tests { "tests" } else { "backend" }.to_string(),
+                        errors: 1,
+                        warnings: 0,
+                        diagnostics: failure.clone(),
+                    });
+                    self.bug_errors = Some(failure);
+                    self.bug_count += 1;
+                    if self.bug_count > run_config().bug_budget {
+                        return Err(Box::new(self.give_up(factsheet)?));
+                    }
+                    self.attributes.state = AgentState::Working;
+                }
+                _ => {
+                    self.attributes.state = AgentState::Finished;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route: &str, method: &str) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: route.contains('{').to_string(),
+            method: method.to_string(),
+            request_body: serde_json::Value::String("None".to_string()),
+            response: serde_json::Value::String("None".to_string()),
+            route: route.to_string(),
+        }
+    }
+
+    #[test]
+    fn tests_finds_uncovered_routes() {
+        let test_code: &str = r#"
+            include!("../src/main.rs");
+
+            #[actix_web::test]
+            async fn tests_get_task() {
+                let req = test::TestRequest::get().uri("/task/1").to_request();
+                let req = TestRequest::post()
+                    .uri( "/task")
+                    .set_json(&task)
+                    .to_request();
+            }"#;
+        assert_eq!(
+            extract_test_requests(test_code),
+            vec![
+                ("get".to_string(), "/task/1".to_string()),
+                ("post".to_string(), "/task".to_string())
+            ]
+        );
+        let routes: Vec<RouteObject> = vec![
+            route("/task", "post"),
+            route("/task/{id}", "get"),
+            route("/task/{id}", "delete"),
+        ];
+        assert_eq!(
+            uncovered_routes(test_code, &routes),
+            vec!["DELETE /task/{id}"]
+        );
+    }
+
+    #[test]
+    fn tests_blames_test_file_only_for_its_errors() {
+        let stderr: &str = "warning: unused import: `async_trait::async_trait`
+ --> tests/../src/main.rs:6:5
+error[E0599]: no function or associated item named `new` found for struct `AppState`
+ --> tests/api_routes.rs:5:36";
+        assert!(has_test_compile_error(stderr));
+        assert!(!has_test_compile_error(
+            "error[E0308]: mismatched types\n --> src/main.rs:80:5"
+        ));
+    }
+}
@@ -1,7 +1,10 @@
+use crate::helpers::diagnostics::{Diagnostic, count_diagnostics};
 use crate::models::agents_basic::basic_agent::BasicAgent;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize, ser};
+use std::path::{Path, PathBuf};
 use std::{fmt::Debug, vec};
+use tokio_util::sync::CancellationToken;
 #[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 pub struct ProjectScope {
     pub is_crud_required: bool,
@@ -17,12 +20,182 @@ pub struct RouteObject {
     pub route: String,
 }
 #[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct EntityField {
+    pub name: String,
+    pub field_type: String,
+    #[serde(default)]
+    pub primary_key: bool,
+    #[serde(default)]
+    pub nullable: bool,
+    #[serde(default)]
+    pub references: Option<String>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct Entity {
+    pub name: String,
+    pub fields: Vec<EntityField>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct DataModel {
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub migrations: Vec<String>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct ReviewFinding {
+    pub severity: Severity,
+    pub category: String,
+    pub description: String,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Default)]
+pub struct CodeReview {
+    pub findings: Vec<ReviewFinding>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct LoadReport {
+    pub label: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub throughput_rps: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct RouteCheck {
+    pub method: String,
+    pub route: String,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub mismatches: Vec<String>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct LifecycleStep {
+    pub resource: String,
+    pub step: String,
+    pub method: String,
+    pub route: String,
+    pub status: Option<u16>,
+    pub passed: bool,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub mismatches: Vec<String>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct DiagnosticCounts {
+    pub agent: String,
+    pub iteration: usize,
+    pub errors: usize,
+    pub warnings: usize,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct GateResult {
+    pub agent: String,
+    pub gate: String,
+    pub passed: bool,
+    pub errors: usize,
+    pub warnings: usize,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct FixAttempt {
+    pub attempt: usize,
+    // "build", the gate that failed or "response shape", "tests" or "backend" for the tester,
+    // "auth checks" for auth
+    pub stage: String,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: String,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct FixReport {
+    pub agent: String,
+    pub bug_budget: u8,
+    pub last_compiling_revision: Option<PathBuf>,
+    pub remaining_errors: String,
+    pub attempts: Vec<FixAttempt>,
+}
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Default)]
 pub struct FactSheet {
     pub project_description: String,
     pub project_scope: Option<ProjectScope>,
     pub external_urls: Option<Vec<String>>,
     pub backend_code: Option<String>,
     pub api_endpoint_schema: Option<Vec<RouteObject>>,
+    #[serde(default)]
+    pub project_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub frontend_code: Option<String>,
+    #[serde(default)]
+    pub data_model: Option<DataModel>,
+    #[serde(default)]
+    pub integration_tests: Option<String>,
+    #[serde(default)]
+    pub code_review: Option<CodeReview>,
+    #[serde(default)]
+    pub user_request: Option<String>,
+    #[serde(default)]
+    pub load_reports: Vec<LoadReport>,
+    #[serde(default)]
+    pub route_checks: Vec<RouteCheck>,
+    #[serde(default)]
+    pub lifecycle_checks: Vec<LifecycleStep>,
+    #[serde(default)]
+    pub diagnostic_counts: Vec<DiagnosticCounts>,
+    #[serde(default)]
+    pub gate_results: Vec<GateResult>,
+    #[serde(default)]
+    pub fix_report: Option<FixReport>,
+}
+
+impl CodeReview {
+    // High severity findings send the backend back for another round
+    pub fn has_high_severity(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.severity == Severity::High)
+    }
+}
+
+impl RouteCheck {
+    // Answered 2xx with the declared response shape
+    pub fn passed(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+            && self.mismatches.is_empty()
+    }
+}
+
+impl FactSheet {
+    // Directory the generated project lives in
+    pub fn project_path(&self) -> &Path {
+        self.project_dir
+            .as_deref()
+            .expect("No project directory on factsheet")
+    }
+
+    // Error and warning counts of one build, numbered per agent
+    pub fn record_diagnostics(&mut self, agent: &str, diagnostics: &[Diagnostic]) {
+        let iteration: usize = self
+            .diagnostic_counts
+            .iter()
+            .filter(|counts| counts.agent == agent)
+            .count()
+            + 1;
+        let (errors, warnings) = count_diagnostics(diagnostics);
+        self.diagnostic_counts.push(DiagnosticCounts {
+            agent: agent.to_string(),
+            iteration,
+            errors,
+            warnings,
+        });
+    }
 }
 
 #[async_trait]
@@ -31,8 +204,10 @@ pub trait SpecialFunctions: Debug {
     fn get_attributes_from_agent(&self) -> &BasicAgent;
 
     // This function will allow agents to execute their logic
+    // Agents stop early once cancel is triggered
     async fn execute(
         &mut self,
         factsheet: &mut FactSheet,
+        cancel: &CancellationToken,
     ) -> Result<(), Box<dyn std::error::Error>>;
 }
@@ -0,0 +1,192 @@
+use crate::models::agents::agent_architect::AgentSolutionArchitect;
+use crate::models::agents::agent_auth::AgentAuthEngineer;
+use crate::models::agents::agent_backend::AgentBackendDeveloper;
+use crate::models::agents::agent_clarifier::AgentRequirementsClarifier;
+use crate::models::agents::agent_database::AgentDatabaseDesigner;
+use crate::models::agents::agent_frontend::AgentFrontendDeveloper;
+use crate::models::agents::agent_packager::AgentPackager;
+use crate::models::agents::agent_reviewer::AgentCodeReviewer;
+use crate::models::agents::agent_tester::AgentTestEngineer;
+use crate::models::agents::agents_traits::SpecialFunctions;
+use std::fmt;
+
+pub type AgentFactory = Box<dyn Fn() -> Box<dyn SpecialFunctions> + Send + Sync>;
+// An agent together with the registry name it was created from
+pub type PipelineAgent = (String, Box<dyn SpecialFunctions>);
+
+// Pipeline used when the configuration does not name one
+pub const DEFAULT_PIPELINE: [&str; 8] = [
+    "clarifier",
+    "architect",
+    "database",
+    "backend",
+    "auth",
+    "tests",
+    "frontend",
+    "package",
+];
+
+// Named agent factories the Managing Agent builds its pipeline from
+pub struct AgentRegistry {
+    factories: Vec<(String, AgentFactory)>,
+}
+
+impl AgentRegistry {
+    // Registry without any agents, use Default for the built in ones
+    pub fn empty() -> Self {
+        Self { factories: vec![] }
+    }
+
+    // Registers a factory, replacing any agent already known by that name
+    pub fn register<F>(&mut self, name: &str, factory: F) -> &mut Self
+    where
+        F: Fn() -> Box<dyn SpecialFunctions> + Send + Sync + 'static,
+    {
+        let factory: AgentFactory = Box::new(factory);
+        match self.factories.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = factory,
+            None => self.factories.push((name.to_string(), factory)),
+        }
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.factories
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn SpecialFunctions>> {
+        self.factories
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, factory)| factory())
+    }
+
+    // Instantiates every agent of the pipeline in order
+    pub fn build_pipeline(
+        &self,
+        pipeline: &[String],
+    ) -> Result<Vec<PipelineAgent>, Box<dyn std::error::Error>> {
+        pipeline
+            .iter()
+            .map(|name| match self.create(name) {
+                Some(agent) => Ok((name.clone(), agent)),
+                None => Err(format!(
+                    "Unknown agent '{}' in pipeline, registered agents are: {}",
+                    name,
+                    self.names().join(", ")
+                )
+                .into()),
+            })
+            .collect()
+    }
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        let mut registry: AgentRegistry = AgentRegistry::empty();
+        registry
+            .register("clarifier", || Box::new(AgentRequirementsClarifier::new()))
+            .register("architect", || Box::new(AgentSolutionArchitect::new()))
+            .register("database", || Box::new(AgentDatabaseDesigner::new()))
+            .register("backend", || Box::new(AgentBackendDeveloper::new()))
+            .register("review", || Box::new(AgentCodeReviewer::new()))
+            .register("auth", || Box::new(AgentAuthEngineer::new()))
+            .register("tests", || Box::new(AgentTestEngineer::new()))
+            .register("frontend", || Box::new(AgentFrontendDeveloper::new()))
+            .register("package", || Box::new(AgentPackager::new()));
+        registry
+    }
+}
+
+impl fmt::Debug for AgentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgentRegistry")
+            .field("agents", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agents::agents_traits::FactSheet;
+    use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+    use async_trait::async_trait;
+    use tokio_util::sync::CancellationToken;
+
+    #[derive(Debug)]
+    struct AgentComplianceChecker {
+        attributes: BasicAgent,
+    }
+
+    #[async_trait]
+    impl SpecialFunctions for AgentComplianceChecker {
+        fn get_attributes_from_agent(&self) -> &BasicAgent {
+            &self.attributes
+        }
+
+        async fn execute(
+            &mut self,
+            _factsheet: &mut FactSheet,
+            _cancel: &CancellationToken,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            self.attributes.state = AgentState::Finished;
+            Ok(())
+        }
+    }
+
+    fn compliance_checker() -> Box<dyn SpecialFunctions> {
+        Box::new(AgentComplianceChecker {
+            attributes: BasicAgent {
+                objective: "Checks compliance".to_string(),
+                position: "Compliance Checker".to_string(),
+                state: AgentState::Discovery,
+                memory: vec![],
+            },
+        })
+    }
+
+    #[test]
+    fn tests_registers_custom_agent() {
+        let mut registry: AgentRegistry = AgentRegistry::default();
+        registry.register("compliance", compliance_checker);
+        assert_eq!(
+            registry.names(),
+            vec![
+                "clarifier",
+                "architect",
+                "database",
+                "backend",
+                "review",
+                "auth",
+                "tests",
+                "frontend",
+                "package",
+                "compliance"
+            ]
+        );
+
+        let pipeline: Vec<String> = vec!["architect".to_string(), "compliance".to_string()];
+        let agents = registry.build_pipeline(&pipeline).unwrap();
+        assert_eq!(
+            agents[0].1.get_attributes_from_agent().position,
+            "Solutions Architect"
+        );
+        assert_eq!(
+            agents[1].1.get_attributes_from_agent().position,
+            "Compliance Checker"
+        );
+    }
+
+    #[test]
+    fn tests_rejects_unknown_agent() {
+        let registry: AgentRegistry = AgentRegistry::default();
+        let err = registry
+            .build_pipeline(&["designer".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("clarifier, architect, database"));
+    }
+}
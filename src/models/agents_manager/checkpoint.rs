@@ -0,0 +1,61 @@
+use crate::models::agents::agents_traits::FactSheet;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+// Snapshot of a run, written after every agent so it can be resumed
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub factsheet: FactSheet,
+    pub completed_agents: Vec<String>,
+}
+
+impl Checkpoint {
+    // Accepts either a checkpoint file or the project directory holding one
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let contents: String = if path.is_dir() {
+            fs::read_to_string(path.join(CHECKPOINT_FILE))?
+        } else {
+            fs::read_to_string(path)?
+        };
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_checkpoint_round_trip() {
+        let checkpoint = Checkpoint {
+            factsheet: FactSheet {
+                project_description: "build a website that lists songs".to_string(),
+                project_scope: None,
+                external_urls: Some(vec![]),
+                ..Default::default()
+            },
+            completed_agents: vec!["architect".to_string()],
+        };
+        let path = std::env::temp_dir()
+            .join(format!("backendbro-checkpoint-{}", std::process::id()))
+            .join(CHECKPOINT_FILE);
+        checkpoint.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+        assert_eq!(
+            Checkpoint::load(path.parent().unwrap()).unwrap(),
+            checkpoint
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
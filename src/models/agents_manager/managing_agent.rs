@@ -1,61 +1,147 @@
 use crate::ai_functions::ai_func_managing::convert_user_input_to_goal;
-use crate::helpers::general::ai_task_request;
-use crate::models::agents::agent_architect::AgentSolutionArchitect;
-use crate::models::agents::agent_backend::AgentBackendDeveloper;
-use crate::models::agents::agents_traits::{FactSheet, SpecialFunctions};
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::config::run_config;
+use crate::helpers::general::{ai_task_request, create_project_dir};
+use crate::helpers::process::check_cancelled;
+use crate::helpers::review::review_factsheet;
+use crate::models::agents::agents_traits::FactSheet;
 use crate::models::agents_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents_manager::agent_registry::{AgentRegistry, PipelineAgent};
+use crate::models::agents_manager::checkpoint::{CHECKPOINT_FILE, Checkpoint};
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
 #[derive(Debug)]
 pub struct ManagingAgent {
     attributes: BasicAgent,
     factsheet: FactSheet,
-    agents: Vec<Box<dyn SpecialFunctions>>,
+    registry: AgentRegistry,
+    // Agent names looked up in the registry, --pipeline unless set with with_pipeline
+    pipeline: Vec<String>,
+    agents: Vec<PipelineAgent>,
+    completed_agents: Vec<String>,
 }
 impl ManagingAgent {
-    pub async fn new(usr_req: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let attributes: BasicAgent = BasicAgent {
+    fn attributes() -> BasicAgent {
+        BasicAgent {
             objective: "Manages agents who're building a excellent website for the user"
                 .to_string(),
             position: "Project Manager".to_string(),
             state: AgentState::Discovery,
             memory: vec![],
-        };
+        }
+    }
+    pub async fn new(usr_req: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let attributes: BasicAgent = Self::attributes();
         let project_description: String = ai_task_request(
-            usr_req,
+            usr_req.clone(),
             attributes.position.as_str(),
             get_function_string!(convert_user_input_to_goal),
             convert_user_input_to_goal,
         )
-        .await;
-        let agents: Vec<Box<dyn SpecialFunctions>> = vec![];
+        .await?;
+        let project_dir: PathBuf = create_project_dir(&run_config().output_dir)?;
+        let project_msg: String = format!("Generating project in {}", project_dir.display());
+        PrintCommand::AICall
+            .print_agent_message(attributes.position.as_str(), project_msg.as_str());
         let factsheet: FactSheet = FactSheet {
             project_description,
             project_scope: None,
             external_urls: None,
             backend_code: None,
             api_endpoint_schema: None,
+            project_dir: Some(project_dir),
+            user_request: Some(usr_req),
+            ..Default::default()
         };
         Ok(Self {
             attributes,
             factsheet,
-            agents,
+            registry: AgentRegistry::default(),
+            pipeline: run_config().pipeline.clone(),
+            agents: vec![],
+            completed_agents: vec![],
         })
     }
-    fn add_agent(&mut self, agent: Box<dyn SpecialFunctions>) {
-        self.agents.push(agent);
+    // Pick up a previous run where it stopped, skipping agents that already finished
+    pub fn from_checkpoint(checkpoint: Checkpoint) -> Self {
+        Self {
+            attributes: Self::attributes(),
+            factsheet: checkpoint.factsheet,
+            registry: AgentRegistry::default(),
+            pipeline: run_config().pipeline.clone(),
+            agents: vec![],
+            completed_agents: checkpoint.completed_agents,
+        }
+    }
+    // Swap in a registry holding custom agents before executing the project
+    pub fn with_registry(mut self, registry: AgentRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+    // Run these agents instead of the configured pipeline
+    pub fn with_pipeline(mut self, pipeline: Vec<String>) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+    fn checkpoint_path(&self) -> PathBuf {
+        self.factsheet.project_path().join(CHECKPOINT_FILE)
     }
-    fn create_agents(&mut self) {
-        self.add_agent(Box::new(AgentSolutionArchitect::new()));
-        // TODO : Add a backend agent
-        self.add_agent(Box::new(AgentBackendDeveloper::new()));
+    fn save_checkpoint(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let checkpoint: Checkpoint = Checkpoint {
+            factsheet: self.factsheet.clone(),
+            completed_agents: self.completed_agents.clone(),
+        };
+        checkpoint.save(&self.checkpoint_path())
+    }
+    fn create_agents(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.agents = self.registry.build_pipeline(&self.pipeline)?;
+        Ok(())
     }
-    pub async fn execute_project(&mut self) {
-        self.create_agents();
-        for agent in &mut self.agents {
-            let agent_res: Result<(), Box<dyn std::error::Error>> =
-                agent.execute(&mut self.factsheet).await;
-            let agent_info = agent.get_attributes_from_agent();
-            // dbg!(agent_info);
+    pub async fn execute_project(
+        &mut self,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.create_agents()?;
+        self.save_checkpoint()?;
+        let mut agents: Vec<PipelineAgent> = std::mem::take(&mut self.agents);
+        for index in 0..agents.len() {
+            let (agent_name, agent) = &mut agents[index];
+            if self.completed_agents.contains(agent_name) {
+                let skip_msg: String = format!("Resuming: skipping {}", agent_name);
+                PrintCommand::AICall
+                    .print_agent_message(self.attributes.position.as_str(), skip_msg.as_str());
+                continue;
+            }
+            // Dropping the agent future on cancel also drops any process guard it holds
+            let agent_res: Result<(), Box<dyn std::error::Error>> = tokio::select! {
+                res = agent.execute(&mut self.factsheet, cancel) => res,
+                _ = cancel.cancelled() => check_cancelled(cancel),
+            };
+            if let Err(err) = agent_res {
+                self.save_checkpoint()?;
+                let stop_msg: String = format!(
+                    "{} stopped: {}. Resume with --resume {}",
+                    agent_name,
+                    err,
+                    self.factsheet.project_path().display()
+                );
+                PrintCommand::Issue
+                    .print_agent_message(self.attributes.position.as_str(), stop_msg.as_str());
+                return Err(err);
+            }
+            self.completed_agents.push(agent_name.clone());
+
+            // Give the user a chance to fix the FactSheet before the next agent uses it
+            if run_config().review
+                && let Some((_, next_agent)) = agents.get(index + 1)
+            {
+                let next_position: &str = next_agent.get_attributes_from_agent().position.as_str();
+                self.factsheet = review_factsheet(&self.factsheet, next_position);
+            }
+            self.save_checkpoint()?;
         }
+        self.agents = agents;
+        Ok(())
     }
 }
 #[cfg(test)]
@@ -67,7 +153,10 @@ mod tests {
         let mut managing_agent: ManagingAgent = ManagingAgent::new(String::from(usr_request))
             .await
             .expect("Error creating managung agents");
-        managing_agent.execute_project().await;
+        managing_agent
+            .execute_project(&CancellationToken::new())
+            .await
+            .expect("Error executing project");
         dbg!(managing_agent.factsheet);
     }
 }
@@ -1 +1,3 @@
+pub mod agent_registry;
+pub mod checkpoint;
 pub mod managing_agent;
@@ -1,3 +1,4 @@
+use crate::helpers::config::run_config;
 use dotenv::dotenv;
 use reqwest::Client;
 use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
@@ -16,9 +17,13 @@ pub async fn send_request(prompt: &str) -> Result<String, Box<dyn Error + Send>>
     let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
-    let client = Client::new();
+    let client = Client::builder()
+        .timeout(run_config().llm_timeout)
+        .build()
+        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
     let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent?key={}",
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+        run_config().model,
         api_key
     );
     let body = serde_json::json!({
@@ -151,6 +151,13 @@ async fn main() -> std::io::Result<()> {
 
     let data: web::Data<AppState> = web::Data::new(AppState { db: Mutex::new(db) });
 
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(8080);
+    // 0.0.0.0 inside a container, set by the Dockerfile
+    let host: String = std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+
     HttpServer::new(move || {
         App::new()
             .wrap(
@@ -173,7 +180,7 @@ async fn main() -> std::io::Result<()> {
             .route("/register", web::post().to(register))
             .route("/login", web::post().to(login))
     })
-    .bind("127.0.0.1:8080")?
+    .bind((host.as_str(), port))?
     .run()
     .await
 }